@@ -0,0 +1,243 @@
+//! Markdown reference for every config key, rendered from the embedded
+//! schema. `docs/config.md` is this output; a test keeps it current, and
+//! another keeps the schema's keys in step with the config structs.
+
+use serde_json::Value;
+
+use crate::{ConfigError, CONFIG_SCHEMA_JSON};
+
+/// Renders the config reference printed by `arbiter config-docs`.
+pub fn config_docs() -> Result<String, ConfigError> {
+    let schema: Value = serde_json::from_str(CONFIG_SCHEMA_JSON)
+        .map_err(|err| ConfigError::SchemaLoad(err.to_string()))?;
+
+    let mut out = String::from(
+        "# Configuration Reference\n\n\
+         Generated by `arbiter config-docs` from `config/config.schema.json`; \
+         do not edit by hand.\n",
+    );
+    if let Some(duration) = schema
+        .pointer("/$defs/duration/description")
+        .and_then(Value::as_str)
+    {
+        out.push_str(&format!(
+            "\nKeys of type `duration`: {duration} Units are `ms`, `s`, `m`, `h` and `d`, \
+             e.g. `\"30s\"`.\n"
+        ));
+    }
+    if let Some(size) = schema
+        .pointer("/$defs/size/description")
+        .and_then(Value::as_str)
+    {
+        out.push_str(&format!(
+            "\nKeys of type `size`: {size} Units are `B`, `KB`, `MB`, `GB`, `KiB`, `MiB` and \
+             `GiB`, e.g. `\"100MiB\"`.\n"
+        ));
+    }
+    render_section(&schema, "", true, &mut out);
+    Ok(out)
+}
+
+/// Writes one table per object, then recurses into its object-typed keys.
+fn render_section(schema: &Value, path: &str, section_required: bool, out: &mut String) {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return;
+    };
+    let required = string_list(schema.get("required"));
+
+    let mut rows = Vec::new();
+    let mut nested = Vec::new();
+    for (key, property) in properties {
+        let key_path = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{path}.{key}")
+        };
+        if is_deprecated(property) {
+            continue;
+        }
+        if property.get("properties").is_some() {
+            nested.push((key_path, property, required.contains(key)));
+            continue;
+        }
+        let mut constraints = constraints(property);
+        if required.contains(key) {
+            constraints.insert(0, "required".to_string());
+        }
+        rows.push(format!(
+            "| `{key_path}` | {} | {} | {} | {} |",
+            type_name(property),
+            property
+                .get("default")
+                .map(|default| format!("`{default}`"))
+                .unwrap_or_default(),
+            constraints.join(", "),
+            description(property),
+        ));
+    }
+
+    if !path.is_empty() {
+        out.push_str(&format!("\n## `{path}`\n"));
+        let mut text = description(schema);
+        if !section_required {
+            text = format!("{text} Optional.").trim_start().to_string();
+        }
+        if !text.is_empty() {
+            out.push('\n');
+            out.push_str(&text);
+            out.push('\n');
+        }
+    }
+    if !rows.is_empty() {
+        out.push_str("\n| Key | Type | Default | Constraints | Description |\n");
+        out.push_str("|---|---|---|---|---|\n");
+        for row in rows {
+            out.push_str(&row);
+            out.push('\n');
+        }
+    }
+    for (key_path, property, required) in nested {
+        render_section(property, &key_path, required, out);
+    }
+}
+
+fn type_name(property: &Value) -> String {
+    if let Some(variants) = property.get("oneOf").and_then(Value::as_array) {
+        if let Some(reference) = variants.iter().find_map(|v| v.get("$ref")?.as_str()) {
+            return reference.trim_start_matches("#/$defs/").to_string();
+        }
+    }
+    match property.get("type") {
+        Some(Value::String(kind)) if kind == "array" => match property.pointer("/items/type") {
+            Some(Value::String(item)) => format!("list of {item}"),
+            _ => "list".to_string(),
+        },
+        Some(Value::String(kind)) => kind.clone(),
+        Some(Value::Array(_)) => string_list(property.get("type")).join(" or "),
+        _ => String::new(),
+    }
+}
+
+fn constraints(property: &Value) -> Vec<String> {
+    let mut out = Vec::new();
+    let integer = property
+        .get("oneOf")
+        .and_then(Value::as_array)
+        .and_then(|variants| variants.iter().find(|v| v.get("type").is_some()))
+        .unwrap_or(property);
+    if let Some(min) = integer.get("minimum") {
+        out.push(format!(">= {min}"));
+    }
+    if let Some(values) = property.get("enum") {
+        out.push(format!("one of {}", code_list(values)));
+    }
+    if let Some(min) = property.get("minItems") {
+        out.push(format!("at least {min} item(s)"));
+    }
+    if property.get("uniqueItems") == Some(&Value::Bool(true)) {
+        out.push("unique".to_string());
+    }
+    if let Some(values) = property.pointer("/items/enum") {
+        out.push(format!("items one of {}", code_list(values)));
+    }
+    if property.pointer("/items/minLength").is_some() {
+        out.push("non-empty items".to_string());
+    }
+    if property.get("minLength").is_some() {
+        out.push("non-empty".to_string());
+    }
+    out
+}
+
+/// Former key names kept so older configs still load; the current key's
+/// row documents the setting.
+fn is_deprecated(property: &Value) -> bool {
+    property.get("deprecated") == Some(&Value::Bool(true))
+}
+
+fn description(value: &Value) -> String {
+    value
+        .get("description")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn code_list(values: &Value) -> String {
+    string_list(Some(values))
+        .iter()
+        .map(|v| format!("`{v}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn committed_config_docs_are_current() {
+        let committed = include_str!("../../../docs/config.md");
+        assert!(
+            config_docs().unwrap() == committed,
+            "docs/config.md is stale; regenerate it with `arbiter config-docs > docs/config.md`"
+        );
+    }
+
+    #[test]
+    fn schema_keys_match_config_structs() {
+        let yaml = format!(
+            "{}\ndeterminism:\n  fixed_time: \"2026-01-01T00:00:00Z\"\n",
+            crate::STARTER_CONFIG_YAML
+        );
+        let cfg = crate::parse_and_validate(&yaml).unwrap();
+        let schema: Value = serde_json::from_str(CONFIG_SCHEMA_JSON).unwrap();
+        let mut mismatches = Vec::new();
+        compare_keys(
+            &schema,
+            &serde_json::to_value(cfg).unwrap(),
+            "",
+            &mut mismatches,
+        );
+        assert!(mismatches.is_empty(), "schema drift: {mismatches:?}");
+    }
+
+    fn compare_keys(schema: &Value, instance: &Value, path: &str, out: &mut Vec<String>) {
+        let (Some(properties), Some(object)) = (
+            schema.get("properties").and_then(Value::as_object),
+            instance.as_object(),
+        ) else {
+            return;
+        };
+        for key in properties
+            .iter()
+            .filter(|(k, property)| !object.contains_key(*k) && !is_deprecated(property))
+            .map(|(k, _)| k)
+        {
+            out.push(format!(
+                "{path}{key} is in the schema but not read by Config"
+            ));
+        }
+        for (key, value) in object {
+            match properties.get(key) {
+                Some(property) => compare_keys(property, value, &format!("{path}{key}."), out),
+                None => out.push(format!(
+                    "{path}{key} is read by Config but not in the schema"
+                )),
+            }
+        }
+    }
+}
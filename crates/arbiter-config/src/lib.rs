@@ -1,6 +1,15 @@
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use thiserror::Error;
 
+mod docs;
+pub mod units;
+pub mod window;
+
+pub use docs::config_docs;
+pub use units::ByteSize;
+pub use window::TimeWindow;
+
 #[derive(Debug, Error)]
 pub enum ConfigError {
     #[error("read config failed: {0}")]
@@ -11,11 +20,14 @@ pub enum ConfigError {
     SchemaLoad(String),
     #[error("schema validation failed: {0}")]
     SchemaValidation(String),
+    #[error("unknown config keys: {}", .0.join(", "))]
+    UnknownKeys(Vec<String>),
     #[error("unsupported config: {0}")]
     UnsupportedConfig(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     pub server: Server,
     pub store: Store,
@@ -23,33 +35,173 @@ pub struct Config {
     pub policy: Policy,
     pub approver: Approver,
     pub audit: Audit,
+    #[serde(default)]
+    pub determinism: Option<Determinism>,
+}
+
+/// Test-only: freezes the clock at `fixed_time` and derives every generated
+/// identifier from `seed`, so identical request sequences produce identical
+/// runs and audit output. `request_delays` lets tests force an interleaving
+/// of concurrent requests through `x-arbiter-test-*` headers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Determinism {
+    pub fixed_time: String,
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub request_delays: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Server {
     pub listen_addr: String,
+    #[serde(default)]
+    pub startup_check: StartupCheck,
+    #[serde(default)]
+    pub reuse_port: bool,
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(
+        default = "default_request_timeout",
+        with = "units::millis",
+        alias = "request_timeout_ms"
+    )]
+    pub request_timeout: Duration,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StartupCheck {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_audit_tail_records")]
+    pub audit_tail_records: u64,
+    #[serde(default = "default_on_violation")]
+    pub on_violation: String,
+}
+
+impl Default for StartupCheck {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            audit_tail_records: default_audit_tail_records(),
+            on_violation: default_on_violation(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Store {
     pub kind: String,
     pub sqlite_path: Option<String>,
+    #[serde(default)]
+    pub backup_dir: Option<String>,
+    #[serde(default, with = "units::optional_hours", alias = "archive_after_hours")]
+    pub archive_after: Option<Duration>,
+    #[serde(
+        default = "default_archive_interval",
+        with = "units::seconds",
+        alias = "archive_interval_seconds"
+    )]
+    pub archive_interval: Duration,
+    #[serde(default)]
+    pub idempotency_journal_path: Option<String>,
+    #[serde(
+        default = "default_idempotency_gc_interval",
+        with = "units::seconds",
+        alias = "idempotency_gc_interval_seconds"
+    )]
+    pub idempotency_gc_interval: Duration,
+    #[serde(default = "default_gc_batch_size")]
+    pub gc_batch_size: u64,
+    #[serde(default)]
+    pub retain_request_payloads: bool,
+    #[serde(
+        default,
+        with = "units::optional_hours",
+        alias = "vacuum_interval_hours"
+    )]
+    pub vacuum_interval: Option<Duration>,
+    #[serde(default)]
+    pub vacuum_window: Option<TimeWindow>,
+    #[serde(default)]
+    pub sqlite: SqliteTuning,
+    #[serde(default)]
+    pub memory: MemoryLimits,
+}
+
+/// Memory store settings. Unset caps mean unbounded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MemoryLimits {
+    #[serde(default)]
+    pub max_idempotency_entries: Option<u64>,
+    /// Caps hot plus archived runs.
+    #[serde(default)]
+    pub max_runs: Option<u64>,
+    /// Snapshot written on graceful shutdown and loaded on start.
+    #[serde(default)]
+    pub snapshot_path: Option<String>,
 }
 
+/// Connection pragmas applied when the SQLite store is opened.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SqliteTuning {
+    #[serde(default = "default_sqlite_journal_mode")]
+    pub journal_mode: String,
+    #[serde(
+        default = "default_sqlite_busy_timeout",
+        with = "units::millis",
+        alias = "busy_timeout_ms"
+    )]
+    pub busy_timeout: Duration,
+    #[serde(default = "default_sqlite_synchronous")]
+    pub synchronous: String,
+    /// Environment variable holding the SQLCipher key. Unset means the
+    /// database is not encrypted.
+    #[serde(default)]
+    pub encryption_key_env: Option<String>,
+}
+
+impl Default for SqliteTuning {
+    fn default() -> Self {
+        Self {
+            journal_mode: default_sqlite_journal_mode(),
+            busy_timeout: default_sqlite_busy_timeout(),
+            synchronous: default_sqlite_synchronous(),
+            encryption_key_env: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Governance {
     pub allowed_providers: Vec<String>,
     #[serde(default)]
     pub capability_allowlist: Vec<String>,
     #[serde(default)]
     pub capability_denylist: Vec<String>,
-    #[serde(default = "default_permit_ttl_seconds")]
-    pub permit_ttl_seconds: u64,
-    #[serde(default = "default_idempotency_retention_hours")]
-    pub idempotency_retention_hours: u64,
+    #[serde(
+        default = "default_permit_ttl",
+        with = "units::seconds",
+        alias = "permit_ttl_seconds"
+    )]
+    pub permit_ttl: Duration,
+    #[serde(
+        default = "default_idempotency_retention",
+        with = "units::hours",
+        alias = "idempotency_retention_hours"
+    )]
+    pub idempotency_retention: Duration,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Policy {
     pub version: String,
     #[serde(default = "default_require_write_external")]
@@ -60,9 +212,12 @@ pub struct Policy {
     pub require_approval_for_start_job: bool,
     #[serde(default = "default_require_production")]
     pub require_approval_for_production: bool,
+    #[serde(default)]
+    pub warn_only_rules: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Approver {
     #[serde(default)]
     pub default_approvers: Vec<String>,
@@ -71,10 +226,85 @@ pub struct Approver {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Audit {
+    #[serde(default = "default_audit_sink")]
+    pub sink: String,
     pub jsonl_path: String,
     #[serde(default)]
     pub immutable_mirror_path: Option<String>,
+    #[serde(default = "default_checkpoint_interval")]
+    pub checkpoint_interval: u64,
+    #[serde(default)]
+    pub checkpoint_publish_url: Option<String>,
+    #[serde(
+        default = "default_checkpoint_publish_interval",
+        with = "units::seconds",
+        alias = "checkpoint_publish_interval_seconds"
+    )]
+    pub checkpoint_publish_interval: Duration,
+    #[serde(default = "default_audit_hash_alg")]
+    pub hash_alg: String,
+    #[serde(default)]
+    pub s3: Option<AuditS3>,
+    #[serde(default)]
+    pub sinks: Vec<AuditSinkEntry>,
+    #[serde(default)]
+    pub rotation: Option<AuditRotation>,
+}
+
+/// Rotation of `audit.jsonl_path`. The file is renamed to
+/// `<jsonl_path>.<n>` once it reaches `max_bytes` or its first record is
+/// `max_age` old, and the newest `keep` rotated files are retained.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditRotation {
+    #[serde(default)]
+    pub max_bytes: Option<ByteSize>,
+    #[serde(default, with = "units::optional_hours", alias = "max_age_hours")]
+    pub max_age: Option<Duration>,
+    #[serde(default = "default_audit_rotation_keep")]
+    pub keep: u32,
+}
+
+/// Extra output every audit record is fanned out to after `audit.sink`.
+/// `on_failure` decides whether a failed write fails the request
+/// (`fail_request`) or is only logged (`best_effort`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditSinkEntry {
+    pub kind: String,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default = "default_audit_sink_on_failure")]
+    pub on_failure: String,
+}
+
+/// Bucket that `audit.sink=s3` ships rotated log segments to. Credentials
+/// are read from the named environment variables, never from the file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditS3 {
+    pub bucket: String,
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    /// S3-compatible endpoint, addressed path-style. Unset means AWS.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    #[serde(
+        default = "default_s3_rotate_interval",
+        with = "units::seconds",
+        alias = "rotate_interval_seconds"
+    )]
+    pub rotate_interval: Duration,
+    #[serde(default = "default_s3_access_key_id_env")]
+    pub access_key_id_env: String,
+    #[serde(default = "default_s3_secret_access_key_env")]
+    pub secret_access_key_env: String,
+    #[serde(default)]
+    pub session_token_env: Option<String>,
 }
 
 fn default_require_write_external() -> bool {
@@ -93,49 +323,216 @@ fn default_require_production() -> bool {
     true
 }
 
-fn default_permit_ttl_seconds() -> u64 {
-    300
+fn default_checkpoint_interval() -> u64 {
+    256
 }
 
-fn default_idempotency_retention_hours() -> u64 {
-    24
+fn default_checkpoint_publish_interval() -> Duration {
+    Duration::from_secs(60)
 }
 
+fn default_request_timeout() -> Duration {
+    Duration::from_millis(30_000)
+}
+
+fn default_audit_sink() -> String {
+    "jsonl".to_string()
+}
+
+fn default_audit_hash_alg() -> String {
+    "sha256".to_string()
+}
+
+fn default_audit_rotation_keep() -> u32 {
+    10
+}
+
+fn default_audit_sink_on_failure() -> String {
+    "fail_request".to_string()
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_s3_rotate_interval() -> Duration {
+    Duration::from_secs(3600)
+}
+
+fn default_s3_access_key_id_env() -> String {
+    "AWS_ACCESS_KEY_ID".to_string()
+}
+
+fn default_s3_secret_access_key_env() -> String {
+    "AWS_SECRET_ACCESS_KEY".to_string()
+}
+
+fn default_idempotency_gc_interval() -> Duration {
+    Duration::from_secs(300)
+}
+
+fn default_gc_batch_size() -> u64 {
+    1000
+}
+
+fn default_sqlite_journal_mode() -> String {
+    "wal".to_string()
+}
+
+fn default_sqlite_busy_timeout() -> Duration {
+    Duration::from_millis(5000)
+}
+
+fn default_sqlite_synchronous() -> String {
+    "full".to_string()
+}
+
+fn default_archive_interval() -> Duration {
+    Duration::from_secs(300)
+}
+
+fn default_audit_tail_records() -> u64 {
+    100
+}
+
+fn default_on_violation() -> String {
+    "refuse".to_string()
+}
+
+fn default_permit_ttl() -> Duration {
+    Duration::from_secs(300)
+}
+
+fn default_idempotency_retention() -> Duration {
+    Duration::from_secs(24 * 3600)
+}
+
+/// JSON schema for the config file, embedded so the binary needs no
+/// `config/` directory at runtime.
+pub const CONFIG_SCHEMA_JSON: &str = include_str!("../../../config/config.schema.json");
+
+/// Starter config written by `arbiter init`.
+pub const STARTER_CONFIG_YAML: &str = include_str!("../../../config/example-config.yaml");
+
 pub fn load_and_validate(path: &str) -> Result<Config, ConfigError> {
     let config_text =
         std::fs::read_to_string(path).map_err(|err| ConfigError::Read(err.to_string()))?;
+    parse_and_validate(&config_text)
+}
+
+/// Rejects keys the schema does not define with `ConfigError::UnknownKeys`,
+/// naming each by its dotted path (e.g. `server.startup_check.enabeld`).
+pub fn parse_and_validate(config_text: &str) -> Result<Config, ConfigError> {
+    parse(config_text, None, false).map(|(cfg, _)| cfg)
+}
+
+/// Deep-merges `overlay` into the config document before validating it, so a
+/// fragment only has to name the keys it changes. Objects merge key by key;
+/// any other value, lists included, replaces the base value.
+pub fn parse_and_validate_with_overlay(
+    config_text: &str,
+    overlay: &serde_json::Value,
+) -> Result<Config, ConfigError> {
+    parse(config_text, Some(overlay), false).map(|(cfg, _)| cfg)
+}
+
+pub fn load_and_validate_lenient(path: &str) -> Result<(Config, Vec<String>), ConfigError> {
+    let config_text =
+        std::fs::read_to_string(path).map_err(|err| ConfigError::Read(err.to_string()))?;
+    parse_and_validate_lenient(&config_text)
+}
+
+/// Like `parse_and_validate`, but drops unknown keys instead of failing and
+/// returns their dotted paths so the caller can warn about them.
+pub fn parse_and_validate_lenient(config_text: &str) -> Result<(Config, Vec<String>), ConfigError> {
+    parse(config_text, None, true)
+}
+
+fn parse(
+    config_text: &str,
+    overlay: Option<&serde_json::Value>,
+    lenient: bool,
+) -> Result<(Config, Vec<String>), ConfigError> {
     let yaml: serde_yaml::Value =
-        serde_yaml::from_str(&config_text).map_err(|err| ConfigError::Parse(err.to_string()))?;
-    let json_value =
+        serde_yaml::from_str(config_text).map_err(|err| ConfigError::Parse(err.to_string()))?;
+    let mut json_value =
         serde_json::to_value(yaml).map_err(|err| ConfigError::Parse(err.to_string()))?;
+    if let Some(overlay) = overlay {
+        merge_overlay(&mut json_value, overlay);
+    }
 
-    validate_against_schema(&json_value)?;
+    let schema: serde_json::Value = serde_json::from_str(CONFIG_SCHEMA_JSON)
+        .map_err(|err| ConfigError::SchemaLoad(err.to_string()))?;
+    let mut unknown = Vec::new();
+    strip_unknown_keys(&schema, &mut json_value, "", &mut unknown);
+    if !unknown.is_empty() && !lenient {
+        return Err(ConfigError::UnknownKeys(unknown));
+    }
+
+    validate_against_schema(&schema, &json_value)?;
 
     let cfg: Config =
         serde_json::from_value(json_value).map_err(|err| ConfigError::Parse(err.to_string()))?;
     validate_runtime_support(&cfg)?;
-    Ok(cfg)
-}
-
-fn validate_against_schema(instance: &serde_json::Value) -> Result<(), ConfigError> {
-    let schema_path = [
-        std::path::PathBuf::from("config/config.schema.json"),
-        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
-            .join("../..")
-            .join("config/config.schema.json"),
-    ]
-    .into_iter()
-    .find(|path| path.exists())
-    .ok_or_else(|| {
-        ConfigError::SchemaLoad("config schema not found at config/config.schema.json".to_string())
-    })?;
-
-    let schema_text = std::fs::read_to_string(schema_path)
-        .map_err(|err| ConfigError::SchemaLoad(err.to_string()))?;
-    let schema: serde_json::Value = serde_json::from_str(&schema_text)
-        .map_err(|err| ConfigError::SchemaLoad(err.to_string()))?;
+    Ok((cfg, unknown))
+}
+
+fn merge_overlay(base: &mut serde_json::Value, overlay: &serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => merge_overlay(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+/// Removes keys from objects whose schema sets `additionalProperties: false`
+/// and does not list them, recording each removed key's dotted path.
+fn strip_unknown_keys(
+    schema: &serde_json::Value,
+    instance: &mut serde_json::Value,
+    prefix: &str,
+    unknown: &mut Vec<String>,
+) {
+    let (Some(properties), Some(object)) = (
+        schema.get("properties").and_then(|p| p.as_object()),
+        instance.as_object_mut(),
+    ) else {
+        return;
+    };
+    let closed = schema.get("additionalProperties") == Some(&serde_json::Value::Bool(false));
+    object.retain(|key, value| {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match properties.get(key) {
+            Some(property) => {
+                strip_unknown_keys(property, value, &path, unknown);
+                true
+            }
+            None if closed => {
+                unknown.push(path);
+                false
+            }
+            None => true,
+        }
+    });
+}
 
-    let validator = jsonschema::validator_for(&schema)
+fn validate_against_schema(
+    schema: &serde_json::Value,
+    instance: &serde_json::Value,
+) -> Result<(), ConfigError> {
+    let validator = jsonschema::validator_for(schema)
         .map_err(|err| ConfigError::SchemaLoad(err.to_string()))?;
     if let Err(first) = validator.validate(instance) {
         return Err(ConfigError::SchemaValidation(first.to_string()));
@@ -163,21 +560,322 @@ fn validate_runtime_support(cfg: &Config) -> Result<(), ConfigError> {
         ));
     }
 
+    // Two memory-store instances keep separate audit chain heads, so
+    // overlapping them on one audit log would fork the chain.
+    if cfg.server.reuse_port && cfg.store.kind == "memory" {
+        return Err(ConfigError::UnsupportedConfig(
+            "server.reuse_port requires store.kind=sqlite; memory-store instances cannot overlap"
+                .to_string(),
+        ));
+    }
+
+    for rule in &cfg.policy.warn_only_rules {
+        if !matches!(
+            rule.as_str(),
+            "provider.allowed_list" | "capability.denylist" | "capability.allowlist"
+        ) {
+            return Err(ConfigError::UnsupportedConfig(format!(
+                "policy.warn_only_rules: unknown deny rule '{rule}'"
+            )));
+        }
+    }
+
+    if cfg.audit.checkpoint_interval == 0 {
+        return Err(ConfigError::UnsupportedConfig(
+            "audit.checkpoint_interval must be > 0".to_string(),
+        ));
+    }
+
+    match cfg.audit.sink.as_str() {
+        "jsonl" => {}
+        "s3" => {
+            let Some(s3) = &cfg.audit.s3 else {
+                return Err(ConfigError::UnsupportedConfig(
+                    "audit.sink=s3 requires audit.s3".to_string(),
+                ));
+            };
+            if s3.bucket.is_empty() {
+                return Err(ConfigError::UnsupportedConfig(
+                    "audit.s3.bucket must not be empty".to_string(),
+                ));
+            }
+            if s3.rotate_interval.is_zero() {
+                return Err(ConfigError::UnsupportedConfig(
+                    "audit.s3.rotate_interval must be > 0".to_string(),
+                ));
+            }
+            if let Some(endpoint) = &s3.endpoint {
+                if !(endpoint.starts_with("http://") || endpoint.starts_with("https://")) {
+                    return Err(ConfigError::UnsupportedConfig(
+                        "audit.s3.endpoint must be an http(s) URL".to_string(),
+                    ));
+                }
+            }
+        }
+        "stdout" => {
+            if cfg.audit.immutable_mirror_path.is_some() {
+                return Err(ConfigError::UnsupportedConfig(
+                    "audit.immutable_mirror_path is not supported with audit.sink=stdout"
+                        .to_string(),
+                ));
+            }
+            if cfg.audit.checkpoint_publish_url.is_some() {
+                return Err(ConfigError::UnsupportedConfig(
+                    "audit.checkpoint_publish_url is not supported with audit.sink=stdout"
+                        .to_string(),
+                ));
+            }
+        }
+        _ => {
+            return Err(ConfigError::UnsupportedConfig(
+                "audit.sink must be jsonl|stdout|s3".to_string(),
+            ));
+        }
+    }
+    if cfg.audit.sink != "s3" && cfg.audit.s3.is_some() {
+        return Err(ConfigError::UnsupportedConfig(
+            "audit.s3 requires audit.sink=s3".to_string(),
+        ));
+    }
+
+    if let Some(rotation) = &cfg.audit.rotation {
+        if rotation.max_bytes.is_none() && rotation.max_age.is_none() {
+            return Err(ConfigError::UnsupportedConfig(
+                "audit.rotation requires max_bytes or max_age".to_string(),
+            ));
+        }
+        if rotation.max_bytes == Some(ByteSize(0))
+            || rotation.max_age.is_some_and(|age| age.is_zero())
+        {
+            return Err(ConfigError::UnsupportedConfig(
+                "audit.rotation.max_bytes and max_age must be > 0".to_string(),
+            ));
+        }
+        if rotation.keep < 1 {
+            return Err(ConfigError::UnsupportedConfig(
+                "audit.rotation.keep must be >= 1; the newest rotated file anchors the chain of the next one".to_string(),
+            ));
+        }
+        if cfg.audit.sink == "stdout" {
+            return Err(ConfigError::UnsupportedConfig(
+                "audit.rotation requires audit.sink=jsonl or s3".to_string(),
+            ));
+        }
+        if cfg.audit.checkpoint_publish_url.is_some() {
+            return Err(ConfigError::UnsupportedConfig(
+                "audit.rotation is not supported with audit.checkpoint_publish_url, whose segment indexes would restart in every file".to_string(),
+            ));
+        }
+    }
+
+    let mut audit_paths = vec![cfg.audit.jsonl_path.as_str()];
+    audit_paths.extend(cfg.audit.immutable_mirror_path.as_deref());
+    let mut stdout_used = cfg.audit.sink == "stdout";
+    for (index, entry) in cfg.audit.sinks.iter().enumerate() {
+        match (entry.kind.as_str(), entry.path.as_deref()) {
+            ("jsonl", Some(path)) => {
+                if audit_paths.contains(&path) {
+                    return Err(ConfigError::UnsupportedConfig(format!(
+                        "audit.sinks[{index}].path {path} is already an audit output"
+                    )));
+                }
+                audit_paths.push(path);
+            }
+            ("jsonl", None) => {
+                return Err(ConfigError::UnsupportedConfig(format!(
+                    "audit.sinks[{index}].path is required when kind=jsonl"
+                )));
+            }
+            ("stdout", None) => {
+                if stdout_used {
+                    return Err(ConfigError::UnsupportedConfig(format!(
+                        "audit.sinks[{index}] writes to stdout, which is already an audit output"
+                    )));
+                }
+                stdout_used = true;
+            }
+            ("stdout", Some(_)) => {
+                return Err(ConfigError::UnsupportedConfig(format!(
+                    "audit.sinks[{index}].path is not supported when kind=stdout"
+                )));
+            }
+            _ => {
+                return Err(ConfigError::UnsupportedConfig(format!(
+                    "audit.sinks[{index}].kind must be jsonl|stdout"
+                )));
+            }
+        }
+        if entry.on_failure != "fail_request" && entry.on_failure != "best_effort" {
+            return Err(ConfigError::UnsupportedConfig(format!(
+                "audit.sinks[{index}].on_failure must be fail_request|best_effort"
+            )));
+        }
+    }
+
+    if cfg.audit.checkpoint_publish_interval.is_zero() {
+        return Err(ConfigError::UnsupportedConfig(
+            "audit.checkpoint_publish_interval must be > 0".to_string(),
+        ));
+    }
+
+    if let Some(url) = &cfg.audit.checkpoint_publish_url {
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err(ConfigError::UnsupportedConfig(
+                "audit.checkpoint_publish_url must be an http(s) URL".to_string(),
+            ));
+        }
+    }
+
+    if cfg.server.request_timeout.is_zero() {
+        return Err(ConfigError::UnsupportedConfig(
+            "server.request_timeout must be greater than 0".to_string(),
+        ));
+    }
+
+    if cfg.audit.hash_alg != "sha256" && cfg.audit.hash_alg != "blake3" {
+        return Err(ConfigError::UnsupportedConfig(
+            "audit.hash_alg must be sha256|blake3".to_string(),
+        ));
+    }
+
+    if let Some(determinism) = &cfg.determinism {
+        if chrono::DateTime::parse_from_rfc3339(&determinism.fixed_time).is_err() {
+            return Err(ConfigError::UnsupportedConfig(
+                "determinism.fixed_time must be an RFC 3339 timestamp".to_string(),
+            ));
+        }
+    }
+
+    if cfg.store.kind == "sqlite" && cfg.store.idempotency_journal_path.is_some() {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.idempotency_journal_path requires store.kind=memory".to_string(),
+        ));
+    }
+
+    if cfg.store.kind == "sqlite" && cfg.store.memory.max_idempotency_entries.is_some() {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.memory.max_idempotency_entries requires store.kind=memory".to_string(),
+        ));
+    }
+
+    if cfg.store.kind == "sqlite" && cfg.store.memory.max_runs.is_some() {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.memory.max_runs requires store.kind=memory".to_string(),
+        ));
+    }
+
+    if cfg.store.kind == "sqlite" && cfg.store.memory.snapshot_path.is_some() {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.memory.snapshot_path requires store.kind=memory".to_string(),
+        ));
+    }
+
+    if cfg.store.memory.snapshot_path.is_some() && cfg.store.idempotency_journal_path.is_some() {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.memory.snapshot_path and store.idempotency_journal_path are mutually exclusive"
+                .to_string(),
+        ));
+    }
+
+    if cfg.store.memory.max_idempotency_entries == Some(0) {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.memory.max_idempotency_entries must be > 0".to_string(),
+        ));
+    }
+
+    if cfg.store.memory.max_runs == Some(0) {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.memory.max_runs must be > 0".to_string(),
+        ));
+    }
+
+    if cfg.store.kind != "sqlite" && cfg.store.sqlite.encryption_key_env.is_some() {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.sqlite.encryption_key_env requires store.kind=sqlite".to_string(),
+        ));
+    }
+
+    if !matches!(
+        cfg.store.sqlite.journal_mode.as_str(),
+        "delete" | "truncate" | "persist" | "wal"
+    ) {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.sqlite.journal_mode must be delete|truncate|persist|wal".to_string(),
+        ));
+    }
+
+    if !matches!(
+        cfg.store.sqlite.synchronous.as_str(),
+        "off" | "normal" | "full" | "extra"
+    ) {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.sqlite.synchronous must be off|normal|full|extra".to_string(),
+        ));
+    }
+
+    if cfg.store.idempotency_gc_interval.is_zero() {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.idempotency_gc_interval must be > 0".to_string(),
+        ));
+    }
+
+    if cfg.store.gc_batch_size == 0 {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.gc_batch_size must be > 0".to_string(),
+        ));
+    }
+
+    if cfg.store.archive_interval.is_zero() {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.archive_interval must be > 0".to_string(),
+        ));
+    }
+
+    if cfg.store.kind != "sqlite" && cfg.store.vacuum_interval.is_some() {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.vacuum_interval requires store.kind=sqlite".to_string(),
+        ));
+    }
+
+    if cfg
+        .store
+        .vacuum_interval
+        .is_some_and(|interval| interval.is_zero())
+    {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.vacuum_interval must be > 0".to_string(),
+        ));
+    }
+
+    if cfg.store.vacuum_window.is_some() && cfg.store.vacuum_interval.is_none() {
+        return Err(ConfigError::UnsupportedConfig(
+            "store.vacuum_window requires store.vacuum_interval".to_string(),
+        ));
+    }
+
+    if cfg.server.startup_check.on_violation != "refuse"
+        && cfg.server.startup_check.on_violation != "repair"
+    {
+        return Err(ConfigError::UnsupportedConfig(
+            "server.startup_check.on_violation must be refuse|repair".to_string(),
+        ));
+    }
+
     if cfg.governance.allowed_providers.is_empty() {
         return Err(ConfigError::UnsupportedConfig(
             "governance.allowed_providers must not be empty".to_string(),
         ));
     }
 
-    if cfg.governance.permit_ttl_seconds == 0 {
+    if cfg.governance.permit_ttl.is_zero() {
         return Err(ConfigError::UnsupportedConfig(
-            "governance.permit_ttl_seconds must be > 0".to_string(),
+            "governance.permit_ttl must be > 0".to_string(),
         ));
     }
 
-    if cfg.governance.idempotency_retention_hours == 0 {
+    if cfg.governance.idempotency_retention.is_zero() {
         return Err(ConfigError::UnsupportedConfig(
-            "governance.idempotency_retention_hours must be > 0".to_string(),
+            "governance.idempotency_retention must be > 0".to_string(),
         ));
     }
 
@@ -0,0 +1,231 @@
+//! Duration and byte-size config values. Each accepts a string with a unit
+//! suffix (`"30s"`, `"15m"`, `"10MB"`) or a bare integer. A bare duration is
+//! read in the unit of the key's former, unit-named spelling, so configs
+//! written before these types existed keep their meaning
+//! (`request_timeout_ms: 30000` and `request_timeout: 30000` are both 30s).
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::time::Duration;
+
+const DURATION_UNITS: [(&str, u64); 5] = [
+    ("d", 86_400_000),
+    ("h", 3_600_000),
+    ("m", 60_000),
+    ("s", 1_000),
+    ("ms", 1),
+];
+
+const SIZE_UNITS: [(&str, u64); 7] = [
+    ("GiB", 1 << 30),
+    ("MiB", 1 << 20),
+    ("KiB", 1 << 10),
+    ("GB", 1_000_000_000),
+    ("MB", 1_000_000),
+    ("KB", 1_000),
+    ("B", 1),
+];
+
+/// Parses `<integer><unit>` with a unit of `ms`, `s`, `m`, `h` or `d`.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let millis = parse_with_units(text, &DURATION_UNITS).ok_or_else(|| {
+        format!("invalid duration {text:?} (expected e.g. 500ms, 30s, 15m, 6h, 7d)")
+    })?;
+    Ok(Duration::from_millis(millis))
+}
+
+/// Formats `duration` with the largest unit that divides it exactly, the
+/// inverse of `parse_duration` at millisecond precision.
+pub fn format_duration(duration: Duration) -> String {
+    format_with_units(duration.as_millis() as u64, &DURATION_UNITS)
+}
+
+/// A size in bytes. Decimal (`KB`, `MB`, `GB`) and binary (`KiB`, `MiB`,
+/// `GiB`) suffixes are both accepted; a bare integer is a byte count.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    pub fn bytes(self) -> u64 {
+        self.0
+    }
+}
+
+impl std::str::FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        text.trim()
+            .parse::<u64>()
+            .ok()
+            .or_else(|| parse_with_units(text, &SIZE_UNITS))
+            .map(ByteSize)
+            .ok_or_else(|| format!("invalid size {text:?} (expected e.g. 512KiB, 10MB, 1GiB)"))
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_with_units(self.0, &SIZE_UNITS))
+    }
+}
+
+impl Serialize for ByteSize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Raw::deserialize(deserializer)? {
+            Raw::Integer(bytes) => Ok(ByteSize(bytes)),
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Raw {
+    Integer(u64),
+    Text(String),
+}
+
+fn parse_with_units(text: &str, units: &[(&str, u64)]) -> Option<u64> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit())?;
+    let (digits, suffix) = text.split_at(split);
+    let (_, scale) = units.iter().find(|(unit, _)| *unit == suffix.trim())?;
+    digits.parse::<u64>().ok()?.checked_mul(*scale)
+}
+
+fn format_with_units(value: u64, units: &[(&str, u64)]) -> String {
+    let (unit, scale) = units
+        .iter()
+        .find(|(_, scale)| value.is_multiple_of(*scale) && (value != 0 || *scale == 1))
+        .copied()
+        .unwrap_or(("", 1));
+    format!("{}{unit}", value / scale)
+}
+
+fn deserialize_in_unit<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    unit_millis: u64,
+) -> Result<Duration, D::Error> {
+    match Raw::deserialize(deserializer)? {
+        Raw::Integer(value) => value
+            .checked_mul(unit_millis)
+            .map(Duration::from_millis)
+            .ok_or_else(|| serde::de::Error::custom(format!("duration {value} is too large"))),
+        Raw::Text(text) => parse_duration(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Writes the suffixed string: key names no longer carry a unit, so a
+/// bare integer would not say what it counts.
+fn serialize_duration<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format_duration(*duration))
+}
+
+macro_rules! duration_unit {
+    ($name:ident, $unit_millis:expr, $doc:literal) => {
+        #[doc = $doc]
+        pub mod $name {
+            use serde::{Deserializer, Serializer};
+            use std::time::Duration;
+
+            pub fn serialize<S: Serializer>(
+                duration: &Duration,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                super::serialize_duration(duration, serializer)
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Duration, D::Error> {
+                super::deserialize_in_unit(deserializer, $unit_millis)
+            }
+        }
+    };
+}
+
+duration_unit!(
+    millis,
+    1,
+    "`serde(with)` for keys whose bare integers are milliseconds."
+);
+duration_unit!(
+    seconds,
+    1_000,
+    "`serde(with)` for keys whose bare integers are seconds."
+);
+duration_unit!(
+    hours,
+    3_600_000,
+    "`serde(with)` for keys whose bare integers are hours."
+);
+
+/// `serde(with)` for optional keys whose bare integers are hours.
+pub mod optional_hours {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => super::serialize_duration(duration, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        #[derive(Deserialize)]
+        struct Hours(#[serde(with = "super::hours")] Duration);
+
+        Ok(Option::<Hours>::deserialize(deserializer)?.map(|Hours(duration)| duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_parse_and_format_with_units() {
+        assert_eq!(
+            parse_duration("1500ms").unwrap(),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(900));
+        assert_eq!(
+            parse_duration("7d").unwrap(),
+            Duration::from_secs(7 * 86_400)
+        );
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("3w").is_err());
+        assert!(parse_duration("-1s").is_err());
+        assert_eq!(format_duration(Duration::from_secs(5400)), "90m");
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1500ms");
+        assert_eq!(format_duration(Duration::ZERO), "0ms");
+    }
+
+    #[test]
+    fn byte_sizes_accept_decimal_and_binary_suffixes() {
+        assert_eq!("10MB".parse::<ByteSize>().unwrap(), ByteSize(10_000_000));
+        assert_eq!("512KiB".parse::<ByteSize>().unwrap(), ByteSize(512 * 1024));
+        assert_eq!("4096".parse::<ByteSize>().unwrap(), ByteSize(4096));
+        assert!("10 mb".parse::<ByteSize>().is_err());
+        assert_eq!(ByteSize(3 << 30).to_string(), "3GiB");
+        let size: ByteSize = serde_json::from_str("4096").unwrap();
+        assert_eq!(size, ByteSize(4096));
+    }
+}
@@ -0,0 +1,95 @@
+//! Daily UTC time windows (`"02:00-05:00"`) for maintenance that should
+//! only run off-peak. A window whose end is before its start wraps past
+//! midnight.
+
+use chrono::{NaiveTime, Timelike};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl TimeWindow {
+    /// Whether `time` falls in the window; the start is inclusive and the
+    /// end exclusive.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl std::str::FromStr for TimeWindow {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid time window {text:?} (expected HH:MM-HH:MM in UTC)");
+        let (start, end) = text.trim().split_once('-').ok_or_else(invalid)?;
+        let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").map_err(|_| invalid())?;
+        let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").map_err(|_| invalid())?;
+        if start == end {
+            return Err(format!("time window {text:?} is empty"));
+        }
+        Ok(TimeWindow { start, end })
+    }
+}
+
+impl fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}-{:02}:{:02}",
+            self.start.hour(),
+            self.start.minute(),
+            self.end.hour(),
+            self.end.minute()
+        )
+    }
+}
+
+impl Serialize for TimeWindow {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeWindow {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn time_windows_parse_and_wrap_past_midnight() {
+        let night: TimeWindow = "23:30-04:00".parse().unwrap();
+        assert!(night.contains(at(23, 30)));
+        assert!(night.contains(at(2, 0)));
+        assert!(!night.contains(at(4, 0)));
+        assert!(!night.contains(at(12, 0)));
+        assert_eq!(night.to_string(), "23:30-04:00");
+
+        let day: TimeWindow = "02:00-05:00".parse().unwrap();
+        assert!(day.contains(at(2, 0)));
+        assert!(!day.contains(at(5, 0)));
+        assert!(!day.contains(at(1, 59)));
+
+        assert!("02:00".parse::<TimeWindow>().is_err());
+        assert!("25:00-03:00".parse::<TimeWindow>().is_err());
+        assert!("03:00-03:00".parse::<TimeWindow>().is_err());
+    }
+}
@@ -21,6 +21,18 @@ pub struct ContractsManifest {
 
 include!(concat!(env!("OUT_DIR"), "/generated_contracts.rs"));
 
+/// Manifest path of the schema every audit log line conforms to.
+pub const AUDIT_RECORD_SCHEMA_REF: &str = "../contracts/v1/ops.audit_record.schema.json";
+
+/// Body of the audit record schema, as served by `GET /v1/contracts`.
+pub fn audit_record_schema() -> &'static str {
+    GENERATED_CONTRACT_SCHEMAS
+        .iter()
+        .find(|(path, _, _)| *path == AUDIT_RECORD_SCHEMA_REF)
+        .map(|(_, _, body)| *body)
+        .expect("audit record schema is in the contracts manifest")
+}
+
 pub fn contracts_manifest_v1() -> ContractsManifest {
     ContractsManifest {
         openapi_sha256: GENERATED_OPENAPI_SHA256,
@@ -172,6 +184,20 @@ pub struct Approval {
     pub decided_at: Option<String>,
     #[serde(default)]
     pub decided_by: Option<String>,
+    #[serde(default)]
+    pub context: Option<ApprovalContext>,
+}
+
+/// What the approver is being asked to allow, captured when the approval is
+/// requested.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApprovalContext {
+    pub objective: String,
+    pub requester: String,
+    pub environment: String,
+    pub intent: StepIntent,
+    pub applied_policies: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -231,6 +257,28 @@ pub struct ApprovalActionRequest {
     pub reason: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyCheckRequest {
+    pub environment: String,
+    pub intent: StepIntent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyCheckResponse {
+    pub effect: DecisionEffect,
+    pub rationale: String,
+    #[serde(default)]
+    pub applied_policies: Vec<String>,
+    #[serde(default)]
+    pub permit_constraints: Value,
+    #[serde(default)]
+    pub required_approvers: Vec<String>,
+    pub environment: String,
+    pub intent: StepIntent,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct AuditEvent {
@@ -246,6 +294,10 @@ pub struct AuditEvent {
     pub payload_hash: String,
     pub prev_hash: String,
     pub hash: String,
+    /// Digest used for `hash` and `payload_hash`. Absent on records written
+    /// before the field existed, which are SHA-256.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alg: Option<String>,
     #[serde(default)]
     pub rationale: Option<String>,
     #[serde(default)]
@@ -257,6 +309,272 @@ pub struct AuditEvent {
 pub struct AuditRunEventsResponse {
     pub run_id: String,
     pub events: Vec<AuditEvent>,
+    /// Older rotated audit files were deleted, so earlier events of the run
+    /// may be missing.
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StoreBackupResponse {
+    pub backup_path: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReadOnlyMode {
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackgroundTaskStatus {
+    pub name: String,
+    pub running: bool,
+    pub passes: u64,
+    #[serde(default)]
+    pub last_started_at: Option<String>,
+    #[serde(default)]
+    pub last_finished_at: Option<String>,
+    pub batches: u64,
+    pub processed: u64,
+    pub total_processed: u64,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackgroundTaskList {
+    pub tasks: Vec<BackgroundTaskStatus>,
+}
+
+/// Panics caught since boot, served by `GET /v1/admin/panics`. The `last_*`
+/// fields describe the most recent one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PanicReport {
+    pub total: u64,
+    #[serde(default)]
+    pub last_request_id: Option<String>,
+    #[serde(default)]
+    pub last_at: Option<String>,
+    /// `<METHOD> <path>` of the request, or `store task`.
+    #[serde(default)]
+    pub last_context: Option<String>,
+    #[serde(default)]
+    pub last_message: Option<String>,
+}
+
+/// One dependency checked by `GET /v1/readyz`. `detail` says why it is not
+/// ready.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ComponentHealth {
+    pub name: String,
+    pub ready: bool,
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+/// Served by `GET /v1/readyz`; `ready` is false when any component is not.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub components: Vec<ComponentHealth>,
+}
+
+/// Row counts served by `GET /v1/admin/store/stats`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StoreStats {
+    pub kind: String,
+    pub runs: u64,
+    pub archived_runs: u64,
+    pub approval_mappings: u64,
+    pub idempotency_records: u64,
+    /// Hot (non-archived) runs keyed by run status.
+    pub runs_by_status: BTreeMap<String, u64>,
+}
+
+/// Portable copy of a store's contents, written by `arbiter store-export`
+/// and `GET /v1/admin/store/export` and read back by the import paths.
+/// Every list is sorted by its key so the same state always exports to the
+/// same document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StoreSnapshot {
+    pub format_version: u32,
+    /// `store.kind` of the store the snapshot was taken from.
+    pub source_kind: String,
+    pub runs: Vec<RunEnvelope>,
+    pub archived_runs: Vec<ArchivedRun>,
+    pub approval_mappings: Vec<ApprovalMapping>,
+    pub idempotency_records: Vec<IdempotencySnapshotRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArchivedRun {
+    pub archived_at: String,
+    pub envelope: RunEnvelope,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApprovalMapping {
+    pub approval_id: String,
+    pub run_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IdempotencySnapshotRecord {
+    pub key: String,
+    pub payload_hash: String,
+    pub response_json: String,
+    pub created_at: String,
+    #[serde(default)]
+    pub payload_json: Option<String>,
+}
+
+/// An idempotency record served by `GET /v1/admin/idempotency/{key}`, with
+/// the stored JSON decoded. `payload` is only present for records written
+/// with `store.retain_request_payloads` on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IdempotencyEntry {
+    pub key: String,
+    pub payload_hash: String,
+    pub created_at: String,
+    pub response: serde_json::Value,
+    #[serde(default)]
+    pub payload: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StoreRepairFinding {
+    #[serde(default)]
+    pub run_id: Option<String>,
+    pub issue: String,
+    pub repaired: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StoreRepairResponse {
+    pub scanned_runs: u64,
+    pub findings: Vec<StoreRepairFinding>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MerkleSiblingSide {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MerkleProofStep {
+    pub hash: String,
+    pub side: MerkleSiblingSide,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditCheckpoint {
+    pub segment_index: u64,
+    pub first_record: u64,
+    pub last_record: u64,
+    pub root: String,
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditCheckpointsResponse {
+    pub checkpoint_interval: u64,
+    pub checkpoints: Vec<AuditCheckpoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditInclusionProof {
+    pub event_id: String,
+    pub record_hash: String,
+    pub leaf_index: u64,
+    pub checkpoint: AuditCheckpoint,
+    pub path: Vec<MerkleProofStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LookupKind {
+    Run,
+    OperationRequest,
+    Step,
+    Decision,
+    Permit,
+    Approval,
+    AuditEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LookupMatch {
+    pub kind: LookupKind,
+    pub run_id: String,
+    pub archived: bool,
+    pub links: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LookupResponse {
+    pub id: String,
+    pub matches: Vec<LookupMatch>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StartupStoreReport {
+    pub kind: String,
+    #[serde(default)]
+    pub schema_version: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditHead {
+    pub records: u64,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StartupReport {
+    pub arbiter_version: String,
+    pub started_at: String,
+    pub config: Value,
+    pub store: StartupStoreReport,
+    pub contracts: ContractsMetadata,
+    pub background_tasks: Vec<String>,
+    pub audit_head: AuditHead,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditVerifyResponse {
+    pub valid: bool,
+    pub from: u64,
+    pub to: u64,
+    pub total_records: u64,
+    #[serde(default)]
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -377,6 +695,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn audit_events_match_audit_record_schema() {
+        let schema: Value = serde_json::from_str(super::audit_record_schema()).unwrap();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        let mut event = super::AuditEvent {
+            event_id: "evt_0123456789abcdef0123456789abcdef".to_string(),
+            event_type: "operation_request_created".to_string(),
+            run_id: "run_0123456789abcdef0123456789abcdef".to_string(),
+            step_id: None,
+            approval_id: None,
+            actor: "api".to_string(),
+            timestamp: "2026-01-01T00:00:00+00:00".to_string(),
+            payload_hash: "a".repeat(64),
+            prev_hash: String::new(),
+            hash: "b".repeat(64),
+            alg: None,
+            rationale: None,
+            policy_refs: vec![],
+        };
+        assert!(validator
+            .validate(&serde_json::to_value(&event).unwrap())
+            .is_ok());
+        event.alg = Some("blake3".to_string());
+        event.prev_hash = "c".repeat(64);
+        assert!(validator
+            .validate(&serde_json::to_value(&event).unwrap())
+            .is_ok());
+        event.hash = "not-a-hash".to_string();
+        assert!(validator
+            .validate(&serde_json::to_value(&event).unwrap())
+            .is_err());
+    }
+
     fn repo_path(relative: &str) -> PathBuf {
         let mut base = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         base.push("../..");
@@ -24,6 +24,7 @@ pub mod policy {
         pub require_approval_for_notify: bool,
         pub require_approval_for_start_job: bool,
         pub require_approval_for_production: bool,
+        pub warn_only_rules: Vec<String>,
     }
 
     #[derive(Debug, Clone)]
@@ -54,44 +55,51 @@ pub mod policy {
         config: &PolicyConfig,
         approvers: Vec<String>,
     ) -> PolicyDecision {
-        if !config
-            .allowed_providers
-            .iter()
-            .any(|v| v == &input.provider)
-        {
-            return PolicyDecision {
-                effect: DecisionEffect::Deny,
-                applied_policies: vec!["provider.allowed_list".to_string()],
-                rationale: format!("provider '{}' is not allowed", input.provider),
-                required_approvers: vec![],
-                permit_constraints: serde_json::json!({}),
-            };
-        }
-
-        if config
-            .capability_denylist
-            .iter()
-            .any(|v| v == &input.capability)
-        {
-            return PolicyDecision {
-                effect: DecisionEffect::Deny,
-                applied_policies: vec!["capability.denylist".to_string()],
-                rationale: format!("capability '{}' is denied", input.capability),
-                required_approvers: vec![],
-                permit_constraints: serde_json::json!({}),
-            };
-        }
+        let violations = [
+            (
+                !config
+                    .allowed_providers
+                    .iter()
+                    .any(|v| v == &input.provider),
+                "provider.allowed_list",
+                format!("provider '{}' is not allowed", input.provider),
+            ),
+            (
+                config
+                    .capability_denylist
+                    .iter()
+                    .any(|v| v == &input.capability),
+                "capability.denylist",
+                format!("capability '{}' is denied", input.capability),
+            ),
+            (
+                !config.capability_allowlist.is_empty()
+                    && !config
+                        .capability_allowlist
+                        .iter()
+                        .any(|v| v == &input.capability),
+                "capability.allowlist",
+                format!("capability '{}' is not allowed", input.capability),
+            ),
+        ];
 
-        if !config.capability_allowlist.is_empty()
-            && !config
-                .capability_allowlist
-                .iter()
-                .any(|v| v == &input.capability)
-        {
+        // Warn-only rules annotate the decision as `would_deny:<rule>` and let
+        // evaluation continue to the next rule.
+        let mut would_deny = Vec::new();
+        for (violated, rule, rationale) in violations {
+            if !violated {
+                continue;
+            }
+            if config.warn_only_rules.iter().any(|r| r == rule) {
+                would_deny.push(format!("would_deny:{rule}"));
+                continue;
+            }
+            let mut applied_policies = would_deny;
+            applied_policies.push(rule.to_string());
             return PolicyDecision {
                 effect: DecisionEffect::Deny,
-                applied_policies: vec!["capability.allowlist".to_string()],
-                rationale: format!("capability '{}' is not allowed", input.capability),
+                applied_policies,
+                rationale,
                 required_approvers: vec![],
                 permit_constraints: serde_json::json!({}),
             };
@@ -107,18 +115,20 @@ pub mod policy {
             || (config.require_approval_for_start_job && start_job)
             || (config.require_approval_for_production && environment == "prod")
         {
+            would_deny.push("approval.required".to_string());
             return PolicyDecision {
                 effect: DecisionEffect::RequireApproval,
-                applied_policies: vec!["approval.required".to_string()],
+                applied_policies: would_deny,
                 rationale: "step requires approval by policy".to_string(),
                 required_approvers: approvers,
                 permit_constraints: serde_json::json!({"approval_required": true}),
             };
         }
 
+        would_deny.push("default.allow".to_string());
         PolicyDecision {
             effect: DecisionEffect::Allow,
-            applied_policies: vec!["default.allow".to_string()],
+            applied_policies: would_deny,
             rationale: "step allowed by policy".to_string(),
             required_approvers: vec![],
             permit_constraints: serde_json::json!({"approval_required": false}),
@@ -142,7 +152,15 @@ pub mod state_machine {
                 | (RunStatus::Running, RunStatus::Succeeded)
                 | (RunStatus::Running, RunStatus::Failed)
                 | (RunStatus::Running, RunStatus::Blocked)
-        ) || matches!(next, RunStatus::Cancelled)
+        ) || (matches!(next, RunStatus::Cancelled) && !is_terminal_run(current))
+    }
+
+    /// Terminal runs keep their outcome; cancelling them would rewrite history.
+    pub fn is_terminal_run(status: &RunStatus) -> bool {
+        matches!(
+            status,
+            RunStatus::Succeeded | RunStatus::Failed | RunStatus::Cancelled
+        )
     }
 
     pub fn can_transition_step(current: &StepStatus, next: &StepStatus) -> bool {
@@ -157,7 +175,17 @@ pub mod state_machine {
                 | (StepStatus::ApprovalRequired, StepStatus::Rejected)
                 | (StepStatus::Permitted, StepStatus::Failed)
                 | (StepStatus::Executing, StepStatus::Failed)
-        ) || matches!(next, StepStatus::Cancelled)
+        ) || (matches!(next, StepStatus::Cancelled) && !is_terminal_step(current))
+    }
+
+    pub fn is_terminal_step(status: &StepStatus) -> bool {
+        matches!(
+            status,
+            StepStatus::Completed
+                | StepStatus::Rejected
+                | StepStatus::Failed
+                | StepStatus::Cancelled
+        )
     }
 
     pub fn can_transition_approval(current: &ApprovalStatus, next: &ApprovalStatus) -> bool {
@@ -170,6 +198,119 @@ pub mod state_machine {
     }
 }
 
+pub mod ids {
+    //! Identifiers supplied by clients end up in idempotency keys joined with
+    //! `:`, so the delimiter is excluded from the allowed charset and every
+    //! composite key stays unambiguous.
+
+    pub const MAX_IDENTIFIER_LEN: usize = 128;
+
+    pub fn validate_identifier(field: &str, value: &str) -> Result<(), String> {
+        if value.is_empty() || value.len() > MAX_IDENTIFIER_LEN {
+            return Err(format!(
+                "{field} must be 1 to {MAX_IDENTIFIER_LEN} characters"
+            ));
+        }
+        if !value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+        {
+            return Err(format!("{field} may only contain [A-Za-z0-9._-]"));
+        }
+        Ok(())
+    }
+}
+
+pub mod merkle {
+    //! Merkle trees over audit record hashes. Leaves are
+    //! `sha256(0x00 || record_hash)` and inner nodes
+    //! `sha256(0x01 || left || right)`, with hashes taken over their lowercase
+    //! hex text. An unpaired node at the end of a level is promoted unchanged.
+
+    use super::sha256_hex;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum SiblingSide {
+        Left,
+        Right,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ProofStep {
+        pub hash: String,
+        pub side: SiblingSide,
+    }
+
+    pub fn leaf_hash(record_hash: &str) -> String {
+        let mut bytes = vec![0x00];
+        bytes.extend_from_slice(record_hash.as_bytes());
+        sha256_hex(&bytes)
+    }
+
+    pub fn node_hash(left: &str, right: &str) -> String {
+        let mut bytes = vec![0x01];
+        bytes.extend_from_slice(left.as_bytes());
+        bytes.extend_from_slice(right.as_bytes());
+        sha256_hex(&bytes)
+    }
+
+    pub fn root(record_hashes: &[String]) -> Option<String> {
+        let mut level: Vec<String> = record_hashes.iter().map(|h| leaf_hash(h)).collect();
+        if level.is_empty() {
+            return None;
+        }
+        while level.len() > 1 {
+            level = next_level(&level);
+        }
+        level.pop()
+    }
+
+    pub fn inclusion_proof(record_hashes: &[String], index: usize) -> Option<Vec<ProofStep>> {
+        if index >= record_hashes.len() {
+            return None;
+        }
+        let mut level: Vec<String> = record_hashes.iter().map(|h| leaf_hash(h)).collect();
+        let mut index = index;
+        let mut path = Vec::new();
+        while level.len() > 1 {
+            if index % 2 == 1 {
+                path.push(ProofStep {
+                    hash: level[index - 1].clone(),
+                    side: SiblingSide::Left,
+                });
+            } else if index + 1 < level.len() {
+                path.push(ProofStep {
+                    hash: level[index + 1].clone(),
+                    side: SiblingSide::Right,
+                });
+            }
+            level = next_level(&level);
+            index /= 2;
+        }
+        Some(path)
+    }
+
+    pub fn verify_inclusion(record_hash: &str, path: &[ProofStep], root: &str) -> bool {
+        let computed = path
+            .iter()
+            .fold(leaf_hash(record_hash), |acc, step| match step.side {
+                SiblingSide::Left => node_hash(&step.hash, &acc),
+                SiblingSide::Right => node_hash(&acc, &step.hash),
+            });
+        computed == root
+    }
+
+    fn next_level(level: &[String]) -> Vec<String> {
+        level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node_hash(left, right),
+                _ => pair[0].clone(),
+            })
+            .collect()
+    }
+}
+
 pub fn parse_rfc3339(ts: &str) -> Option<DateTime<Utc>> {
     chrono::DateTime::parse_from_rfc3339(ts)
         .ok()
@@ -177,9 +318,45 @@ pub fn parse_rfc3339(ts: &str) -> Option<DateTime<Utc>> {
 }
 
 pub fn jcs_sha256_hex(value: &Value) -> Result<String, String> {
-    let canonical = serde_jcs::to_string(value)
-        .map_err(|err| format!("failed to canonicalize JSON via JCS: {err}"))?;
-    Ok(sha256_hex(canonical.as_bytes()))
+    jcs_hash_hex(HashAlg::Sha256, value)
+}
+
+/// Digest used for audit record and payload hashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlg {
+    Sha256,
+    Blake3,
+}
+
+impl HashAlg {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "sha256" => Some(Self::Sha256),
+            "blake3" => Some(Self::Blake3),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Blake3 => "blake3",
+        }
+    }
+}
+
+/// RFC 8785 (JCS) serialization of `value`, the bytes every hash here is
+/// taken over.
+pub fn jcs_canonical(value: &Value) -> Result<String, String> {
+    serde_jcs::to_string(value).map_err(|err| format!("failed to canonicalize JSON via JCS: {err}"))
+}
+
+pub fn jcs_hash_hex(alg: HashAlg, value: &Value) -> Result<String, String> {
+    let canonical = jcs_canonical(value)?;
+    Ok(match alg {
+        HashAlg::Sha256 => sha256_hex(canonical.as_bytes()),
+        HashAlg::Blake3 => blake3::hash(canonical.as_bytes()).to_hex().to_string(),
+    })
 }
 
 fn sha256_hex(bytes: &[u8]) -> String {
@@ -199,6 +376,18 @@ mod tests {
     use arbiter_contracts::{ApprovalStatus, DecisionEffect, RunStatus, StepStatus};
     use serde_json::json;
 
+    #[test]
+    fn hash_algorithms_produce_distinct_digests() {
+        let value = json!({"a": 1});
+        let sha = jcs_hash_hex(HashAlg::Sha256, &value).unwrap();
+        let blake = jcs_hash_hex(HashAlg::Blake3, &value).unwrap();
+        assert_eq!(sha, jcs_sha256_hex(&value).unwrap());
+        assert_eq!(blake.len(), 64);
+        assert_ne!(sha, blake);
+        assert_eq!(HashAlg::parse("blake3"), Some(HashAlg::Blake3));
+        assert_eq!(HashAlg::parse("md5"), None);
+    }
+
     #[test]
     fn jcs_hash_is_order_independent() {
         let a = json!({"b":1,"a":2});
@@ -206,6 +395,62 @@ mod tests {
         assert_eq!(jcs_sha256_hex(&a).unwrap(), jcs_sha256_hex(&b).unwrap());
     }
 
+    #[test]
+    fn identifiers_reject_delimiters_and_oversize_values() {
+        assert!(ids::validate_identifier("request_id", "req-1.a_b").is_ok());
+        assert!(ids::validate_identifier("request_id", "").is_err());
+        assert!(ids::validate_identifier("step_id", "a:b").is_err());
+        assert!(ids::validate_identifier("step_id", "step 1").is_err());
+        assert!(ids::validate_identifier("step_id", &"a".repeat(128)).is_ok());
+        assert!(ids::validate_identifier("step_id", &"a".repeat(129)).is_err());
+    }
+
+    #[test]
+    fn terminal_states_cannot_be_cancelled() {
+        assert!(can_transition_run(
+            &RunStatus::Blocked,
+            &RunStatus::Cancelled
+        ));
+        for status in [
+            RunStatus::Succeeded,
+            RunStatus::Failed,
+            RunStatus::Cancelled,
+        ] {
+            assert!(!can_transition_run(&status, &RunStatus::Cancelled));
+        }
+        assert!(can_transition_step(
+            &StepStatus::ApprovalRequired,
+            &StepStatus::Cancelled
+        ));
+        for status in [
+            StepStatus::Completed,
+            StepStatus::Rejected,
+            StepStatus::Failed,
+            StepStatus::Cancelled,
+        ] {
+            assert!(!can_transition_step(&status, &StepStatus::Cancelled));
+        }
+    }
+
+    #[test]
+    fn merkle_proofs_verify_for_every_leaf() {
+        let hashes: Vec<String> = (0..7)
+            .map(|i| sha256_hex(format!("{i}").as_bytes()))
+            .collect();
+        let root = merkle::root(&hashes).unwrap();
+        for (idx, hash) in hashes.iter().enumerate() {
+            let path = merkle::inclusion_proof(&hashes, idx).unwrap();
+            assert!(merkle::verify_inclusion(hash, &path, &root));
+            assert!(!merkle::verify_inclusion(
+                &hashes[(idx + 1) % 7],
+                &path,
+                &root
+            ));
+        }
+        assert!(merkle::inclusion_proof(&hashes, 7).is_none());
+        assert!(merkle::root(&[]).is_none());
+    }
+
     #[test]
     fn parse_rfc3339_works() {
         assert!(parse_rfc3339("2026-01-01T00:00:00Z").is_some());
@@ -249,6 +494,7 @@ mod tests {
             require_approval_for_notify: false,
             require_approval_for_start_job: false,
             require_approval_for_production: true,
+            warn_only_rules: vec![],
         };
         let approver_cfg = ApproverResolverConfig {
             default_approvers: vec!["team-lead".to_string()],
@@ -280,6 +526,7 @@ mod tests {
             require_approval_for_notify: true,
             require_approval_for_start_job: false,
             require_approval_for_production: false,
+            warn_only_rules: vec![],
         };
         let approver_cfg = ApproverResolverConfig {
             default_approvers: vec!["team-lead".to_string()],
@@ -299,4 +546,36 @@ mod tests {
         );
         assert_eq!(decision.effect, DecisionEffect::RequireApproval);
     }
+
+    #[test]
+    fn warn_only_rule_annotates_instead_of_denying() {
+        let mut cfg = PolicyConfig {
+            allowed_providers: vec!["generic".to_string()],
+            capability_allowlist: vec![],
+            capability_denylist: vec!["drop_table".to_string()],
+            require_approval_for_write_external: false,
+            require_approval_for_notify: false,
+            require_approval_for_start_job: false,
+            require_approval_for_production: false,
+            warn_only_rules: vec!["capability.denylist".to_string()],
+        };
+        let input = PolicyInput {
+            provider: "generic".to_string(),
+            capability: "drop_table".to_string(),
+            intent_type: "change".to_string(),
+            risk_level: "low".to_string(),
+            metadata: json!({}),
+        };
+        let decision = evaluate(&input, "dev", &cfg, vec![]);
+        assert_eq!(decision.effect, DecisionEffect::Allow);
+        assert_eq!(
+            decision.applied_policies,
+            vec!["would_deny:capability.denylist", "default.allow"]
+        );
+
+        cfg.warn_only_rules.clear();
+        let decision = evaluate(&input, "dev", &cfg, vec![]);
+        assert_eq!(decision.effect, DecisionEffect::Deny);
+        assert_eq!(decision.applied_policies, vec!["capability.denylist"]);
+    }
 }
@@ -0,0 +1,237 @@
+use arbiter_contracts::{
+    AuditVerifyResponse, BackgroundTaskList, IdempotencyEntry, PanicReport, ReadOnlyMode,
+    StartupReport, StoreBackupResponse, StoreRepairFinding, StoreRepairResponse, StoreSnapshot,
+    StoreStats,
+};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Deserialize;
+
+use crate::audit::verify_audit_range;
+
+use crate::errors::{into_error, ApiErrorResponse, ApiFailure};
+use crate::integrity::check_store;
+use crate::store::AppState;
+
+pub(crate) async fn backup_store(
+    State(state): State<AppState>,
+) -> Result<(StatusCode, Json<StoreBackupResponse>), ApiErrorResponse> {
+    let backup_dir = state
+        .backup_dir()
+        .ok_or_else(|| ApiFailure::conflict("conflict", "store.backup_dir is not configured"))
+        .map_err(into_error)?;
+    let created_at = state.clock().now();
+    let backup_path = std::path::Path::new(backup_dir)
+        .join(format!(
+            "arbiter-store-{}.db",
+            created_at.format("%Y%m%dT%H%M%S%3fZ")
+        ))
+        .to_string_lossy()
+        .to_string();
+
+    let target = backup_path.clone();
+    state
+        .with_store(move |store| store.backup(&target))
+        .await
+        .and_then(|done| done)
+        .map_err(into_error)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(StoreBackupResponse {
+            backup_path,
+            created_at: created_at.to_rfc3339(),
+        }),
+    ))
+}
+
+pub(crate) async fn store_stats(
+    State(state): State<AppState>,
+) -> Result<Json<StoreStats>, ApiErrorResponse> {
+    let stats = state
+        .with_store(|store| store.stats())
+        .await
+        .and_then(|stats| stats)
+        .map_err(into_error)?;
+    Ok(Json(stats))
+}
+
+pub(crate) async fn get_store_snapshot(
+    State(state): State<AppState>,
+) -> Result<Json<StoreSnapshot>, ApiErrorResponse> {
+    let snapshot = state
+        .with_store(|store| store.export_snapshot())
+        .await
+        .and_then(|snapshot| snapshot)
+        .map_err(into_error)?;
+    Ok(Json(snapshot))
+}
+
+/// Seeds an empty store from a snapshot and returns the resulting counts.
+pub(crate) async fn import_store_snapshot(
+    State(state): State<AppState>,
+    Json(snapshot): Json<StoreSnapshot>,
+) -> Result<Json<StoreStats>, ApiErrorResponse> {
+    let stats = state
+        .with_store(move |store| {
+            store.import_snapshot(snapshot)?;
+            store.stats()
+        })
+        .await
+        .and_then(|stats| stats)
+        .map_err(into_error)?;
+    Ok(Json(stats))
+}
+
+pub(crate) async fn repair_approvals(
+    State(state): State<AppState>,
+) -> Result<Json<StoreRepairResponse>, ApiErrorResponse> {
+    let report = state
+        .with_store(|store| check_store(store, true))
+        .await
+        .and_then(|report| report)
+        .map_err(into_error)?;
+    Ok(Json(StoreRepairResponse {
+        scanned_runs: report.scanned_runs as u64,
+        findings: report
+            .findings
+            .into_iter()
+            .map(|f| StoreRepairFinding {
+                run_id: f.run_id,
+                issue: f.issue,
+                repaired: f.repaired,
+            })
+            .collect(),
+    }))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct AuditVerifyQuery {
+    #[serde(default)]
+    from: Option<usize>,
+    #[serde(default)]
+    to: Option<usize>,
+}
+
+pub(crate) async fn verify_audit(
+    State(state): State<AppState>,
+    Query(query): Query<AuditVerifyQuery>,
+) -> Result<Json<AuditVerifyResponse>, ApiErrorResponse> {
+    let report = state
+        .with_store(move |store| {
+            store
+                .audit_log()
+                .and_then(|path| verify_audit_range(path, query.from, query.to))
+        })
+        .await
+        .and_then(|report| report)
+        .map_err(into_error)?;
+    Ok(Json(AuditVerifyResponse {
+        valid: report.result.is_ok(),
+        from: report.from as u64,
+        to: report.to as u64,
+        total_records: report.total_records as u64,
+        error: report.result.err(),
+    }))
+}
+
+pub(crate) async fn get_startup_report(
+    State(state): State<AppState>,
+) -> Result<Json<StartupReport>, ApiErrorResponse> {
+    let report = state
+        .startup_report()
+        .cloned()
+        .ok_or_else(|| ApiFailure::internal("startup report is not available"))
+        .map_err(into_error)?;
+    Ok(Json(report))
+}
+
+pub(crate) async fn list_tasks(State(state): State<AppState>) -> Json<BackgroundTaskList> {
+    Json(BackgroundTaskList {
+        tasks: state.tasks().list(),
+    })
+}
+
+/// Fetches one idempotency record for debugging a `409 conflict`: the stored
+/// response and, when it was retained, the request body it was keyed on.
+pub(crate) async fn get_idempotency_entry(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+) -> Result<Json<IdempotencyEntry>, ApiErrorResponse> {
+    let lookup_key = key.clone();
+    let record = state
+        .with_store(move |store| store.get_idempotency(&lookup_key))
+        .await
+        .and_then(|record| record)
+        .map_err(into_error)?
+        .ok_or_else(|| ApiFailure::not_found("not_found", "idempotency record not found"))
+        .map_err(into_error)?;
+    let decode = |text: &str| {
+        serde_json::from_str(text).map_err(|err| {
+            into_error(ApiFailure::internal(&format!(
+                "idempotency record decode failed: {err}"
+            )))
+        })
+    };
+    Ok(Json(IdempotencyEntry {
+        response: decode(&record.response_json)?,
+        payload: record.payload_json.as_deref().map(decode).transpose()?,
+        key,
+        payload_hash: record.payload_hash,
+        created_at: record.created_at,
+    }))
+}
+
+pub(crate) async fn get_panics(State(state): State<AppState>) -> Json<PanicReport> {
+    Json(state.panics().report())
+}
+
+pub(crate) async fn get_read_only(State(state): State<AppState>) -> Json<ReadOnlyMode> {
+    Json(ReadOnlyMode {
+        read_only: state.is_read_only(),
+    })
+}
+
+/// A change of mode is audited as a `read_only_set` record; setting the
+/// current mode again writes nothing. The mode is switched under the store
+/// lock, so concurrent toggles are recorded in the order they apply.
+pub(crate) async fn set_read_only(
+    State(state): State<AppState>,
+    Json(input): Json<ReadOnlyMode>,
+) -> Result<Json<ReadOnlyMode>, ApiErrorResponse> {
+    let read_only = input.read_only;
+    let mode = state.clone();
+    state
+        .with_store(move |store| {
+            if mode.is_read_only() == read_only {
+                return Ok(());
+            }
+            store.record_read_only(read_only)?;
+            mode.set_read_only(read_only);
+            Ok(())
+        })
+        .await
+        .and_then(|outcome| outcome)
+        .map_err(into_error)?;
+    Ok(Json(input))
+}
+
+/// Guards the mutating routes. While read-only mode is on they answer 503
+/// without touching the store; reads, audit queries and admin reads stay up.
+pub(crate) async fn reject_when_read_only(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if state.is_read_only() {
+        return into_error(ApiFailure::unavailable(
+            "read_only_mode",
+            "arbiter is in read-only mode; mutating requests are rejected",
+        ))
+        .into_response();
+    }
+    next.run(request).await
+}
@@ -1,13 +1,26 @@
-use arbiter_contracts::{AuditEvent, AuditRunEventsResponse};
-use arbiter_kernel::jcs_sha256_hex;
-use chrono::Utc;
+use arbiter_contracts::{
+    AuditCheckpoint, AuditCheckpointsResponse, AuditEvent, AuditHead, AuditInclusionProof,
+    AuditRunEventsResponse, MerkleProofStep, MerkleSiblingSide,
+};
+use arbiter_kernel::merkle::{self, SiblingSide};
+use arbiter_kernel::{jcs_hash_hex, HashAlg};
 use serde_json::{json, Value};
-use std::io::Write;
+use std::io::{BufRead, Write};
 use std::path::Path;
-use uuid::Uuid;
+use std::time::Duration;
 
+use crate::clock::Clock;
 use crate::errors::ApiFailure;
 
+/// Event type of the record that switches the chain to a new hash
+/// algorithm. It links to the previous record's hash and is itself hashed
+/// with the new algorithm.
+pub(crate) const AUDIT_REANCHOR_EVENT: &str = "audit_reanchored";
+
+/// Event type of the first record in a file started by rotation. It is
+/// chained to the last record of the rotated file like any other record.
+pub(crate) const AUDIT_ROTATED_EVENT: &str = "audit_rotated";
+
 #[derive(Debug, Clone)]
 pub(crate) struct AuditRecord {
     pub event_type: String,
@@ -35,115 +48,549 @@ impl AuditRecord {
     }
 }
 
+/// Where new audit records are written: the primary output named by
+/// `audit.sink`, which anchors the chain and serves reads, then every
+/// fan-out output in order.
+#[derive(Debug, Clone)]
+pub(crate) struct AuditSink {
+    primary: AuditOutput,
+    fanout: Vec<AuditFanout>,
+    rotation: Option<AuditRotation>,
+}
+
+/// `audit.rotation`, applied to the primary jsonl file before each write.
+#[derive(Debug, Clone)]
+struct AuditRotation {
+    max_bytes: Option<u64>,
+    max_age: Option<Duration>,
+    keep: usize,
+    /// False with `audit.sink=s3`, where the uploader deletes rotated files
+    /// once they are shipped instead.
+    prune: bool,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum AuditOutput {
+    Jsonl(String),
+    /// Records go to stdout and nowhere else, so nothing in the server can
+    /// read them back.
+    Stdout,
+}
+
+/// An `audit.sinks` entry, or the `audit.immutable_mirror_path` copy. A
+/// failed write to a best-effort output is logged and the request goes on.
+#[derive(Debug, Clone)]
+pub(crate) struct AuditFanout {
+    pub(crate) name: String,
+    pub(crate) output: AuditOutput,
+    pub(crate) best_effort: bool,
+}
+
+impl AuditSink {
+    pub(crate) fn from_config(audit: &arbiter_config::Audit) -> Self {
+        let primary = match audit.sink.as_str() {
+            "stdout" => AuditOutput::Stdout,
+            _ => AuditOutput::Jsonl(audit.jsonl_path.clone()),
+        };
+        let mut fanout = Vec::new();
+        if let Some(path) = &audit.immutable_mirror_path {
+            fanout.push(AuditFanout {
+                name: "audit_mirror".to_string(),
+                output: AuditOutput::Jsonl(path.clone()),
+                best_effort: false,
+            });
+        }
+        for (index, entry) in audit.sinks.iter().enumerate() {
+            let output = match (entry.kind.as_str(), &entry.path) {
+                ("jsonl", Some(path)) => AuditOutput::Jsonl(path.clone()),
+                _ => AuditOutput::Stdout,
+            };
+            fanout.push(AuditFanout {
+                name: format!("audit_sinks[{index}]"),
+                output,
+                best_effort: entry.on_failure == "best_effort",
+            });
+        }
+        let rotation = audit.rotation.as_ref().map(|rotation| AuditRotation {
+            max_bytes: rotation.max_bytes.map(|size| size.bytes()),
+            max_age: rotation.max_age,
+            keep: rotation.keep as usize,
+            prune: audit.sink != "s3",
+        });
+        Self {
+            primary,
+            fanout,
+            rotation,
+        }
+    }
+
+    /// The log file audit reads are served from, if records are kept.
+    pub(crate) fn path(&self) -> Option<&str> {
+        match &self.primary {
+            AuditOutput::Jsonl(path) => Some(path),
+            AuditOutput::Stdout => None,
+        }
+    }
+
+    /// The last record of the chain: the tail of the log file or, when a
+    /// rotation left no live file behind, of the newest rotated file.
+    pub(crate) fn read_tail(&self) -> Result<Option<AuditEvent>, ApiFailure> {
+        let Some(path) = self.path() else {
+            return Ok(None);
+        };
+        let tail = read_audit_tail(path)?;
+        if tail.is_some() || self.rotation.is_none() {
+            return Ok(tail);
+        }
+        match rotated_files(path)
+            .map_err(|err| ApiFailure::internal(&err))?
+            .last()
+        {
+            Some(seq) => read_audit_tail(&format!("{path}.{seq}")),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn primary(&self) -> &AuditOutput {
+        &self.primary
+    }
+
+    pub(crate) fn fanout(&self) -> &[AuditFanout] {
+        &self.fanout
+    }
+}
+
+impl AuditOutput {
+    fn write(&self, event: &AuditEvent) -> Result<(), ApiFailure> {
+        match self {
+            AuditOutput::Jsonl(path) => append_jsonl_line(path, event),
+            AuditOutput::Stdout => write_stdout_line(event),
+        }
+    }
+
+    /// Whether a record could be written now; stdout is always assumed to be.
+    pub(crate) fn check_writable(&self) -> Result<(), String> {
+        match self {
+            AuditOutput::Jsonl(path) => check_audit_writable(path),
+            AuditOutput::Stdout => Ok(()),
+        }
+    }
+}
+
+/// Writes `record` to every output of `sink`, first rotating the primary
+/// file if `audit.rotation` says it is due.
 pub(crate) fn append_audit_record(
-    path: &str,
-    mirror_path: Option<&str>,
-    last_hash: &str,
+    sink: &AuditSink,
+    last_hash: &mut String,
+    record: AuditRecord,
+    alg: HashAlg,
+    clock: &Clock,
+) -> Result<AuditEvent, ApiFailure> {
+    if let (AuditOutput::Jsonl(path), Some(rotation)) = (&sink.primary, &sink.rotation) {
+        if let Some((rotated, reason)) = rotate_if_due(path, rotation, last_hash, clock)? {
+            let mut link = AuditRecord::new(
+                AUDIT_ROTATED_EVENT,
+                "",
+                "arbiter",
+                json!({ "previous_file": rotated, "reason": reason }),
+            );
+            link.rationale = Some(format!("audit log rotated to {rotated} ({reason})"));
+            write_audit_record(sink, last_hash, link, alg, clock)?;
+        }
+    }
+    write_audit_record(sink, last_hash, record, alg, clock)
+}
+
+/// Advances `last_hash` as soon as the primary output has the record, so a
+/// failed fan-out write fails the request without forking the chain for
+/// the next record.
+fn write_audit_record(
+    sink: &AuditSink,
+    last_hash: &mut String,
     record: AuditRecord,
+    alg: HashAlg,
+    clock: &Clock,
 ) -> Result<AuditEvent, ApiFailure> {
-    let timestamp = Utc::now().to_rfc3339();
     let payload_hash =
-        jcs_sha256_hex(&record.payload).map_err(|err| ApiFailure::internal(&err.to_string()))?;
-    let event_id = format!("evt_{}", Uuid::new_v4().simple());
-    let seed = json!({
-        "event_id": event_id,
-        "event_type": record.event_type,
-        "run_id": record.run_id,
-        "step_id": record.step_id,
-        "approval_id": record.approval_id,
-        "actor": record.actor,
-        "timestamp": timestamp,
-        "payload_hash": payload_hash,
-        "prev_hash": last_hash,
-        "rationale": record.rationale,
-        "policy_refs": record.policy_refs,
-    });
-    let hash = jcs_sha256_hex(&seed).map_err(|err| ApiFailure::internal(&err.to_string()))?;
-
-    let event = AuditEvent {
-        event_id: seed["event_id"].as_str().unwrap_or_default().to_string(),
-        event_type: seed["event_type"].as_str().unwrap_or_default().to_string(),
-        run_id: seed["run_id"].as_str().unwrap_or_default().to_string(),
-        step_id: seed
-            .get("step_id")
-            .and_then(|v| v.as_str())
-            .map(|v| v.to_string()),
-        approval_id: seed
-            .get("approval_id")
-            .and_then(|v| v.as_str())
-            .map(|v| v.to_string()),
-        actor: seed["actor"].as_str().unwrap_or_default().to_string(),
-        timestamp: seed["timestamp"].as_str().unwrap_or_default().to_string(),
-        payload_hash: seed["payload_hash"]
-            .as_str()
-            .unwrap_or_default()
-            .to_string(),
-        prev_hash: seed["prev_hash"].as_str().unwrap_or_default().to_string(),
-        hash,
-        rationale: seed
-            .get("rationale")
-            .and_then(|v| v.as_str())
-            .map(|v| v.to_string()),
-        policy_refs: seed
-            .get("policy_refs")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect::<Vec<_>>()
-            })
-            .unwrap_or_default(),
+        jcs_hash_hex(alg, &record.payload).map_err(|err| ApiFailure::internal(&err.to_string()))?;
+    let mut event = AuditEvent {
+        event_id: clock.id("evt"),
+        event_type: record.event_type,
+        run_id: record.run_id,
+        step_id: record.step_id,
+        approval_id: record.approval_id,
+        actor: record.actor,
+        timestamp: clock.now().to_rfc3339(),
+        payload_hash,
+        prev_hash: last_hash.clone(),
+        hash: String::new(),
+        alg: Some(alg.as_str().to_string()),
+        rationale: record.rationale,
+        policy_refs: record.policy_refs,
     };
+    event.hash = event_hash(&event).map_err(|err| ApiFailure::internal(&err))?;
+    #[cfg(debug_assertions)]
+    check_audit_record_schema(&event)?;
 
-    append_jsonl_line(path, &event)?;
-    if let Some(path) = mirror_path {
-        append_jsonl_line(path, &event)?;
+    sink.primary.write(&event)?;
+    last_hash.clone_from(&event.hash);
+    for fanout in &sink.fanout {
+        match fanout.output.write(&event) {
+            Ok(()) => {}
+            Err(err) if fanout.best_effort => {
+                eprintln!(
+                    "{} write failed for {}: {err:?}",
+                    fanout.name, event.event_id
+                );
+            }
+            Err(err) => return Err(err),
+        }
     }
     Ok(event)
 }
 
-pub(crate) fn read_audit_tail_hash(path: &str) -> Result<String, ApiFailure> {
+/// Renames a due `path` to `<path>.<n>`, one past the highest rotated
+/// file, and deletes the oldest rotated files beyond `keep` unless the
+/// uploader prunes them. Returns the
+/// rotated file's name and which limit was reached. A missing or empty
+/// `path` while the chain has a head means a rotation was interrupted
+/// before its link record was written; the newest rotated file is
+/// returned so the link is written now.
+fn rotate_if_due(
+    path: &str,
+    rotation: &AuditRotation,
+    last_hash: &str,
+    clock: &Clock,
+) -> Result<Option<(String, &'static str)>, ApiFailure> {
+    let len = match std::fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => 0,
+        Err(err) => {
+            return Err(ApiFailure::internal(&format!(
+                "failed to stat audit file: {err}"
+            )))
+        }
+    };
+    if len == 0 {
+        if last_hash.is_empty() {
+            return Ok(None);
+        }
+        let rotated = rotated_files(path).map_err(|err| ApiFailure::internal(&err))?;
+        return Ok(rotated
+            .last()
+            .map(|seq| (rotated_name(&format!("{path}.{seq}")), "interrupted")));
+    }
+    let reason = if rotation.max_bytes.is_some_and(|max| len >= max) {
+        "max_bytes"
+    } else if rotation.max_age.is_some() && first_record_age(path, clock)? >= rotation.max_age {
+        "max_age"
+    } else {
+        return Ok(None);
+    };
+
+    let mut rotated = rotated_files(path).map_err(|err| ApiFailure::internal(&err))?;
+    let next = rotated.last().map_or(1, |last| last + 1);
+    let target = format!("{path}.{next}");
+    std::fs::rename(path, &target)
+        .map_err(|err| ApiFailure::internal(&format!("failed to rotate audit file: {err}")))?;
+    rotated.push(next);
+    if !rotation.prune {
+        return Ok(Some((rotated_name(&target), reason)));
+    }
+    let excess = rotated.len().saturating_sub(rotation.keep);
+    for seq in &rotated[..excess] {
+        if let Err(err) = std::fs::remove_file(format!("{path}.{seq}")) {
+            eprintln!("failed to delete rotated audit file {path}.{seq}: {err}");
+        }
+    }
+    Ok(Some((rotated_name(&target), reason)))
+}
+
+fn rotated_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn first_record_age(path: &str, clock: &Clock) -> Result<Option<Duration>, ApiFailure> {
+    let file = std::fs::File::open(path)
+        .map_err(|err| ApiFailure::internal(&format!("failed to open audit file: {err}")))?;
+    let mut line = String::new();
+    std::io::BufReader::new(file)
+        .read_line(&mut line)
+        .map_err(|err| ApiFailure::internal(&format!("failed to read audit file: {err}")))?;
+    let event: AuditEvent = serde_json::from_str(&line)
+        .map_err(|err| ApiFailure::internal(&format!("invalid audit line: {err}")))?;
+    let written = chrono::DateTime::parse_from_rfc3339(&event.timestamp)
+        .map_err(|err| ApiFailure::internal(&format!("invalid audit timestamp: {err}")))?;
+    Ok((clock.now() - written.to_utc()).to_std().ok())
+}
+
+/// Sequence numbers of the `<path>.<n>` files next to `path`, ascending.
+pub(crate) fn rotated_files(path: &str) -> Result<Vec<u64>, String> {
+    let file = Path::new(path);
+    let dir = match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let prefix = format!(
+        "{}.",
+        file.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    );
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("failed to list {}: {err}", dir.display())),
+    };
+    let mut seqs: Vec<u64> = entries
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().to_string_lossy().to_string();
+            name.strip_prefix(&prefix)?.parse().ok()
+        })
+        .collect();
+    seqs.sort_unstable();
+    Ok(seqs)
+}
+
+/// The file an `audit_rotated` record at the start of `path` links to:
+/// the highest `<path>.<n>` for the live log, `<base>.<n-1>` for a rotated
+/// file. `None` when it has been deleted.
+fn previous_rotated_file(path: &str) -> Option<String> {
+    if let Some((base, seq)) = path.rsplit_once('.') {
+        if let Ok(seq) = seq.parse::<u64>() {
+            let previous = format!("{base}.{}", seq.checked_sub(1)?);
+            return Path::new(&previous).exists().then_some(previous);
+        }
+    }
+    let last = *rotated_files(path).ok()?.last()?;
+    Some(format!("{path}.{last}"))
+}
+
+/// Debug builds check every record against the published audit record
+/// contract before writing it, so a field change that would break
+/// downstream log consumers fails in tests instead of in production.
+#[cfg(debug_assertions)]
+fn check_audit_record_schema(event: &AuditEvent) -> Result<(), ApiFailure> {
+    use std::sync::OnceLock;
+
+    static VALIDATOR: OnceLock<jsonschema::Validator> = OnceLock::new();
+    let validator = VALIDATOR.get_or_init(|| {
+        let schema: Value = serde_json::from_str(arbiter_contracts::audit_record_schema())
+            .expect("audit record schema is valid JSON");
+        jsonschema::validator_for(&schema).expect("audit record schema compiles")
+    });
+    let instance =
+        serde_json::to_value(event).map_err(|err| ApiFailure::internal(&err.to_string()))?;
+    validator.validate(&instance).map_err(|err| {
+        ApiFailure::internal(&format!(
+            "audit record {} does not match the audit record schema: {err}",
+            event.event_type
+        ))
+    })
+}
+
+pub(crate) fn read_audit_tail(path: &str) -> Result<Option<AuditEvent>, ApiFailure> {
     if !Path::new(path).exists() {
-        return Ok(String::new());
+        return Ok(None);
     }
     let lines = read_jsonl(path).map_err(|err| ApiFailure::internal(&err))?;
     let Some(last) = lines.last() else {
-        return Ok(String::new());
+        return Ok(None);
     };
-    let value: Value =
-        serde_json::from_str(last).map_err(|err| ApiFailure::internal(&err.to_string()))?;
-    Ok(value
-        .get("hash")
-        .and_then(Value::as_str)
-        .unwrap_or_default()
-        .to_string())
+    serde_json::from_str(last)
+        .map(Some)
+        .map_err(|err| ApiFailure::internal(&format!("invalid audit line: {err}")))
+}
+
+/// Algorithm of an existing record; records without `alg` predate the field
+/// and are SHA-256.
+pub(crate) fn record_alg(event: &AuditEvent) -> Result<HashAlg, String> {
+    match event.alg.as_deref() {
+        None => Ok(HashAlg::Sha256),
+        Some(value) => {
+            HashAlg::parse(value).ok_or_else(|| format!("unsupported hash algorithm {value}"))
+        }
+    }
+}
+
+pub(crate) fn read_audit_head(path: &str) -> Result<AuditHead, ApiFailure> {
+    let hashes = read_record_hashes(path)?;
+    Ok(AuditHead {
+        records: hashes.len() as u64,
+        hash: hashes.last().cloned().unwrap_or_default(),
+    })
+}
+
+/// Looks `event_id` up in the live file and the rotated files still kept.
+/// The flag is true when the record is in the live file, the only one
+/// inclusion proofs cover.
+pub(crate) fn find_audit_event(
+    path: &str,
+    event_id: &str,
+) -> Result<Option<(AuditEvent, bool)>, ApiFailure> {
+    let files = audit_files(path).map_err(|err| ApiFailure::internal(&err))?;
+    for file in files.iter().rev() {
+        for event in read_events(file)? {
+            if event.event_id == event_id {
+                return Ok(Some((event, file == path)));
+            }
+        }
+    }
+    Ok(None)
 }
 
+/// Events of `run_id` from the rotated files still kept and the live file,
+/// oldest first. `truncated` is set when the oldest kept file starts with an
+/// `audit_rotated` link, i.e. older files were deleted and the run may have
+/// events that are no longer on disk.
 pub(crate) fn list_run_events(
     path: &str,
     run_id: &str,
 ) -> Result<AuditRunEventsResponse, ApiFailure> {
-    if !Path::new(path).exists() {
-        return Ok(AuditRunEventsResponse {
-            run_id: run_id.to_string(),
-            events: vec![],
-        });
-    }
-    let lines = read_jsonl(path).map_err(|err| ApiFailure::internal(&err))?;
     let mut events = Vec::new();
-    for line in &lines {
-        let event: AuditEvent = serde_json::from_str(line)
-            .map_err(|err| ApiFailure::internal(&format!("invalid audit line: {err}")))?;
-        if event.run_id == run_id {
-            events.push(event);
+    let mut truncated = false;
+    let files = audit_files(path).map_err(|err| ApiFailure::internal(&err))?;
+    for (idx, file) in files.iter().enumerate() {
+        for (line, event) in read_events(file)?.into_iter().enumerate() {
+            if idx == 0 && line == 0 && event.event_type == AUDIT_ROTATED_EVENT {
+                truncated = true;
+            }
+            if event.run_id == run_id {
+                events.push(event);
+            }
         }
     }
     Ok(AuditRunEventsResponse {
         run_id: run_id.to_string(),
         events,
+        truncated,
+    })
+}
+
+/// The audit files on disk, oldest first: the kept `<path>.<n>` rotated
+/// files, then `path` itself if it exists.
+pub(crate) fn audit_files(path: &str) -> Result<Vec<String>, String> {
+    let mut files: Vec<String> = rotated_files(path)?
+        .into_iter()
+        .map(|seq| format!("{path}.{seq}"))
+        .collect();
+    if Path::new(path).exists() {
+        files.push(path.to_string());
+    }
+    Ok(files)
+}
+
+fn read_events(path: &str) -> Result<Vec<AuditEvent>, ApiFailure> {
+    read_jsonl(path)
+        .map_err(|err| ApiFailure::internal(&err))?
+        .iter()
+        .map(|line| {
+            serde_json::from_str(line)
+                .map_err(|err| ApiFailure::internal(&format!("invalid audit line: {err}")))
+        })
+        .collect()
+}
+
+/// Merkle roots over consecutive segments of `interval` records. The last
+/// segment is reported with `complete=false` until it fills up.
+pub(crate) fn list_checkpoints(
+    path: &str,
+    interval: u64,
+) -> Result<AuditCheckpointsResponse, ApiFailure> {
+    let hashes = read_record_hashes(path)?;
+    let checkpoints = hashes
+        .chunks(interval as usize)
+        .enumerate()
+        .filter_map(|(idx, segment)| checkpoint_for(idx, segment, interval))
+        .collect();
+    Ok(AuditCheckpointsResponse {
+        checkpoint_interval: interval,
+        checkpoints,
+    })
+}
+
+pub(crate) fn inclusion_proof(
+    path: &str,
+    event_id: &str,
+    interval: u64,
+) -> Result<Option<AuditInclusionProof>, ApiFailure> {
+    let lines = if Path::new(path).exists() {
+        read_jsonl(path).map_err(|err| ApiFailure::internal(&err))?
+    } else {
+        vec![]
+    };
+    let events = lines
+        .iter()
+        .map(|line| serde_json::from_str::<AuditEvent>(line))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| ApiFailure::internal(&format!("invalid audit line: {err}")))?;
+    let Some(position) = events.iter().position(|e| e.event_id == event_id) else {
+        return Ok(None);
+    };
+    let segment_index = position / interval as usize;
+    let leaf_index = position % interval as usize;
+    let hashes: Vec<String> = events
+        .iter()
+        .skip(segment_index * interval as usize)
+        .take(interval as usize)
+        .map(|e| e.hash.clone())
+        .collect();
+    let checkpoint = checkpoint_for(segment_index, &hashes, interval)
+        .ok_or_else(|| ApiFailure::internal("empty audit segment"))?;
+    let path = merkle::inclusion_proof(&hashes, leaf_index)
+        .ok_or_else(|| ApiFailure::internal("audit record outside its segment"))?
+        .into_iter()
+        .map(|step| MerkleProofStep {
+            hash: step.hash,
+            side: match step.side {
+                SiblingSide::Left => MerkleSiblingSide::Left,
+                SiblingSide::Right => MerkleSiblingSide::Right,
+            },
+        })
+        .collect();
+    Ok(Some(AuditInclusionProof {
+        event_id: event_id.to_string(),
+        record_hash: hashes[leaf_index].clone(),
+        leaf_index: leaf_index as u64,
+        checkpoint,
+        path,
+    }))
+}
+
+fn checkpoint_for(
+    segment_index: usize,
+    hashes: &[String],
+    interval: u64,
+) -> Option<AuditCheckpoint> {
+    let root = merkle::root(hashes)?;
+    let first_record = (segment_index as u64) * interval + 1;
+    Some(AuditCheckpoint {
+        segment_index: segment_index as u64,
+        first_record,
+        last_record: first_record + hashes.len() as u64 - 1,
+        root,
+        complete: hashes.len() as u64 == interval,
     })
 }
 
+fn read_record_hashes(path: &str) -> Result<Vec<String>, ApiFailure> {
+    if !Path::new(path).exists() {
+        return Ok(vec![]);
+    }
+    let lines = read_jsonl(path).map_err(|err| ApiFailure::internal(&err))?;
+    lines
+        .iter()
+        .map(|line| {
+            serde_json::from_str::<AuditEvent>(line)
+                .map(|event| event.hash)
+                .map_err(|err| ApiFailure::internal(&format!("invalid audit line: {err}")))
+        })
+        .collect()
+}
+
 pub fn verify_audit_chain(path: &str) -> Result<String, String> {
     verify_audit_chain_with_mirror(path, None)
 }
@@ -153,55 +600,206 @@ pub fn verify_audit_chain_with_mirror(
     mirror_path: Option<&str>,
 ) -> Result<String, String> {
     let main_lines = read_jsonl(path)?;
-    let mut prev_hash = String::new();
-    for (idx, line) in main_lines.iter().enumerate() {
-        let event: AuditEvent = serde_json::from_str(line)
-            .map_err(|err| format!("invalid json at line {}: {err}", idx + 1))?;
-        if event.prev_hash != prev_hash {
+    let anchor = rotation_anchor(path, &main_lines)?;
+    let rotated = anchor.is_some();
+    verify_lines(&main_lines, 0, Some(anchor.unwrap_or_default()))?;
+
+    // The mirror is never rotated, so it ends with the live log instead.
+    if let Some(mirror) = mirror_path {
+        let mirror_lines = read_jsonl(mirror)?;
+        let matches = if rotated {
+            mirror_lines.ends_with(&main_lines)
+        } else {
+            main_lines == mirror_lines
+        };
+        if !matches {
+            return Err("mirror mismatch: audit and mirror contents differ".to_string());
+        }
+    }
+
+    Ok(format!(
+        "audit chain verified: {} records",
+        main_lines.len()
+    ))
+}
+
+/// The hash a log started by rotation links to, checked against the last
+/// record of the previous file while that file is still kept. `None` for
+/// a log that starts the chain.
+fn rotation_anchor(path: &str, lines: &[String]) -> Result<Option<String>, String> {
+    let Some(first) = lines.first() else {
+        return Ok(None);
+    };
+    let event: AuditEvent =
+        serde_json::from_str(first).map_err(|err| format!("invalid json at line 1: {err}"))?;
+    if event.event_type != AUDIT_ROTATED_EVENT {
+        return Ok(None);
+    }
+    if let Some(previous) = previous_rotated_file(path) {
+        let tail = read_audit_tail(&previous)
+            .map_err(|err| format!("failed to read {previous}: {err:?}"))?
+            .map(|tail| tail.hash)
+            .unwrap_or_default();
+        if tail != event.prev_hash {
             return Err(format!(
-                "hash chain mismatch at line {}: expected prev_hash {}, got {}",
-                idx + 1,
-                prev_hash,
+                "hash chain mismatch at line 1: {previous} ends with {tail}, got prev_hash {}",
                 event.prev_hash
             ));
         }
-        let seed = json!({
-            "event_id": event.event_id,
-            "event_type": event.event_type,
-            "run_id": event.run_id,
-            "step_id": event.step_id,
-            "approval_id": event.approval_id,
-            "actor": event.actor,
-            "timestamp": event.timestamp,
-            "payload_hash": event.payload_hash,
-            "prev_hash": event.prev_hash,
-            "rationale": event.rationale,
-            "policy_refs": event.policy_refs,
+    }
+    Ok(Some(event.prev_hash))
+}
+
+/// Verifies only the last `records` entries of the log. The first entry in the
+/// window is trusted for its `prev_hash`; every later link and hash is checked.
+pub(crate) fn verify_audit_tail(path: &str, records: usize) -> Result<usize, String> {
+    if !Path::new(path).exists() {
+        return Ok(0);
+    }
+    let lines = read_jsonl(path)?;
+    let start = lines.len().saturating_sub(records);
+    verify_lines(&lines[start..], start, None)?;
+    Ok(lines.len() - start)
+}
+
+pub(crate) const AUDIT_VERIFY_MAX_RANGE: usize = 10_000;
+
+#[derive(Debug, Clone)]
+pub(crate) struct AuditRangeVerification {
+    pub from: usize,
+    pub to: usize,
+    pub total_records: usize,
+    pub result: Result<(), String>,
+}
+
+/// Verifies records `from..=to` (1-based). The window is anchored to the
+/// record before `from`, or to the genesis hash when `from` is 1.
+pub(crate) fn verify_audit_range(
+    path: &str,
+    from: Option<usize>,
+    to: Option<usize>,
+) -> Result<AuditRangeVerification, ApiFailure> {
+    let lines = if Path::new(path).exists() {
+        read_jsonl(path).map_err(|err| ApiFailure::internal(&err))?
+    } else {
+        vec![]
+    };
+    let total_records = lines.len();
+    if total_records == 0 && from.is_none() && to.is_none() {
+        return Ok(AuditRangeVerification {
+            from: 0,
+            to: 0,
+            total_records,
+            result: Ok(()),
         });
-        let recalculated = jcs_sha256_hex(&seed)
-            .map_err(|err| format!("failed to hash record at line {}: {err}", idx + 1))?;
+    }
+    let from = from.unwrap_or(1);
+    let to = to.unwrap_or(total_records);
+    if from == 0 || to < from {
+        return Err(ApiFailure::bad_request(
+            "invalid_request",
+            "audit range requires 1 <= from <= to",
+        ));
+    }
+    if to > total_records {
+        return Err(ApiFailure::bad_request(
+            "invalid_request",
+            &format!("audit range ends past the last record ({total_records})"),
+        ));
+    }
+    if to - from + 1 > AUDIT_VERIFY_MAX_RANGE {
+        return Err(ApiFailure::bad_request(
+            "invalid_request",
+            &format!("audit range may cover at most {AUDIT_VERIFY_MAX_RANGE} records"),
+        ));
+    }
+
+    let anchor = if from == 1 {
+        rotation_anchor(path, &lines).map(Option::unwrap_or_default)
+    } else {
+        serde_json::from_str::<AuditEvent>(&lines[from - 2])
+            .map(|event| event.hash)
+            .map_err(|err| format!("invalid json at line {}: {err}", from - 1))
+    };
+    let result = anchor.and_then(|prev| verify_lines(&lines[from - 1..to], from - 1, Some(prev)));
+    Ok(AuditRangeVerification {
+        from,
+        to,
+        total_records,
+        result,
+    })
+}
+
+fn verify_lines(
+    lines: &[String],
+    line_offset: usize,
+    expected_prev: Option<String>,
+) -> Result<(), String> {
+    let mut prev_hash = expected_prev;
+    let mut prev_alg: Option<HashAlg> = None;
+    for (idx, line) in lines.iter().enumerate() {
+        let line_no = line_offset + idx + 1;
+        let event: AuditEvent = serde_json::from_str(line)
+            .map_err(|err| format!("invalid json at line {line_no}: {err}"))?;
+        if let Some(prev_hash) = prev_hash.as_deref() {
+            if event.prev_hash != prev_hash {
+                return Err(format!(
+                    "hash chain mismatch at line {line_no}: expected prev_hash {}, got {}",
+                    prev_hash, event.prev_hash
+                ));
+            }
+        }
+        let alg = record_alg(&event).map_err(|err| format!("{err} at line {line_no}"))?;
+        if prev_alg.is_some_and(|prev| prev != alg) && event.event_type != AUDIT_REANCHOR_EVENT {
+            return Err(format!(
+                "hash algorithm changed at line {line_no} without a {AUDIT_REANCHOR_EVENT} record"
+            ));
+        }
+        let recalculated = event_hash(&event)
+            .map_err(|err| format!("failed to hash record at line {line_no}: {err}"))?;
         if recalculated != event.hash {
             return Err(format!(
-                "record hash mismatch at line {}: expected {}, got {}",
-                idx + 1,
-                event.hash,
-                recalculated
+                "record hash mismatch at line {line_no}: expected {}, got {}",
+                event.hash, recalculated
             ));
         }
-        prev_hash = event.hash;
+        prev_hash = Some(event.hash);
+        prev_alg = Some(alg);
     }
+    Ok(())
+}
 
-    if let Some(mirror) = mirror_path {
-        let mirror_lines = read_jsonl(mirror)?;
-        if main_lines != mirror_lines {
-            return Err("mirror mismatch: audit and mirror contents differ".to_string());
-        }
+/// Hashes a record with its own algorithm. `alg` is only part of the hashed
+/// seed when present, so records written before the field still verify.
+fn event_hash(event: &AuditEvent) -> Result<String, String> {
+    let mut seed = json!({
+        "event_id": event.event_id,
+        "event_type": event.event_type,
+        "run_id": event.run_id,
+        "step_id": event.step_id,
+        "approval_id": event.approval_id,
+        "actor": event.actor,
+        "timestamp": event.timestamp,
+        "payload_hash": event.payload_hash,
+        "prev_hash": event.prev_hash,
+        "rationale": event.rationale,
+        "policy_refs": event.policy_refs,
+    });
+    if let Some(alg) = &event.alg {
+        seed["alg"] = json!(alg);
     }
+    jcs_hash_hex(record_alg(event)?, &seed)
+}
 
-    Ok(format!(
-        "audit chain verified: {} records",
-        main_lines.len()
-    ))
+/// Opens `path` for appending, as the next audit write would, without
+/// writing anything.
+fn check_audit_writable(path: &str) -> Result<(), String> {
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map(|_| ())
+        .map_err(|err| format!("failed to open {path} for append: {err}"))
 }
 
 fn append_jsonl_line(path: &str, entry: &AuditEvent) -> Result<(), ApiFailure> {
@@ -220,7 +818,20 @@ fn append_jsonl_line(path: &str, entry: &AuditEvent) -> Result<(), ApiFailure> {
     Ok(())
 }
 
-fn read_jsonl(path: &str) -> Result<Vec<String>, String> {
+/// Writes the record and its newline in one call on the locked handle, so
+/// concurrent output cannot split a line.
+fn write_stdout_line(entry: &AuditEvent) -> Result<(), ApiFailure> {
+    let mut line = serde_json::to_string(entry)
+        .map_err(|err| ApiFailure::internal(&format!("failed to encode audit entry: {err}")))?;
+    line.push('\n');
+    let mut stdout = std::io::stdout().lock();
+    stdout
+        .write_all(line.as_bytes())
+        .and_then(|()| stdout.flush())
+        .map_err(|err| ApiFailure::internal(&format!("failed to write audit entry: {err}")))
+}
+
+pub(crate) fn read_jsonl(path: &str) -> Result<Vec<String>, String> {
     let text =
         std::fs::read_to_string(path).map_err(|err| format!("read failed for {path}: {err}"))?;
     Ok(text
@@ -0,0 +1,266 @@
+use arbiter_config::Config;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::audit::{audit_files, verify_audit_chain};
+use crate::store::AppState;
+
+/// Bumped whenever the backup directory layout changes; restore rejects others.
+/// Version 1 had no rotated audit files and is still accepted.
+const BACKUP_FORMAT_VERSION: u32 = 2;
+const MANIFEST_FILE: &str = "manifest.json";
+const STORE_FILE: &str = "store.db";
+const AUDIT_FILE: &str = "audit.jsonl";
+/// A server rotating or pruning the audit log during the copy leaves a set
+/// whose files no longer link up; the copy is retried this many times.
+const AUDIT_COPY_ATTEMPTS: usize = 3;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BackupManifest {
+    format_version: u32,
+    created_at: String,
+    schema_version: Option<i64>,
+    audit_records: usize,
+    audit_head: String,
+    /// Rotated audit files, oldest first, named `audit.jsonl.<n>` after the
+    /// `<audit.jsonl_path>.<n>` file they were copied from.
+    #[serde(default)]
+    rotated_audit_files: Vec<String>,
+}
+
+/// Writes the sqlite store and the audit log into `out_dir` for
+/// `arbiter backup`. The store is copied first with the SQLite online backup
+/// API and the audit log second, so every change in the store copy has its
+/// audit record in the log copy; the log may also hold records of later
+/// changes. Rotated audit files are copied with the live one, and only
+/// complete audit lines are copied.
+pub async fn backup_to_dir(cfg: Config, out_dir: &str) -> Result<String, String> {
+    crate::reject_offline_memory_store(&cfg, "GET /v1/admin/store/export")?;
+    if cfg.audit.sink == "stdout" {
+        return Err(
+            "audit.sink=stdout keeps no audit log to back up; use arbiter store-backup".to_string(),
+        );
+    }
+    let dir = Path::new(out_dir);
+    let occupied = std::fs::read_dir(dir)
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    if occupied {
+        return Err(format!("backup directory is not empty: {out_dir}"));
+    }
+    std::fs::create_dir_all(dir).map_err(|err| format!("failed to create {out_dir}: {err}"))?;
+
+    let audit_path = cfg.audit.jsonl_path.clone();
+    let (state, _store_lock) = crate::open_for_reading(cfg)?;
+    let created_at = state.clock().now().to_rfc3339();
+    let store = state.lock_store().await;
+    let store_path = dir.join(STORE_FILE);
+    store
+        .backup(&store_path.to_string_lossy())
+        .map_err(|err| format!("backup failed: {err:?}"))?;
+    let schema_version = store.schema_version();
+    let copied = (1..AUDIT_COPY_ATTEMPTS).fold(copy_audit_files(&audit_path, dir), |copied, _| {
+        copied.or_else(|_| copy_audit_files(&audit_path, dir))
+    });
+    drop(store);
+    let (names, audit_records, audit_head) = copied?;
+
+    let manifest = BackupManifest {
+        format_version: BACKUP_FORMAT_VERSION,
+        created_at,
+        schema_version,
+        audit_records,
+        audit_head,
+        rotated_audit_files: names[..names.len() - 1].to_vec(),
+    };
+    let text = serde_json::to_string_pretty(&manifest)
+        .map_err(|err| format!("failed to encode manifest: {err}"))?;
+    let manifest_path = dir.join(MANIFEST_FILE);
+    std::fs::write(&manifest_path, text + "\n")
+        .map_err(|err| format!("failed to write {}: {err}", manifest_path.display()))?;
+    Ok(format!(
+        "backup written: {out_dir} (audit_records={audit_records})"
+    ))
+}
+
+/// Copies every file of the audit log at `audit_path` into `dir`, rotated
+/// files as `audit.jsonl.<n>` and the live file as `audit.jsonl`, each cut
+/// after its last complete line. Returns the names oldest first, ending with
+/// `audit.jsonl`, with the record count and head hash of the copied set. The
+/// copies are verified before returning, so a rotation or prune that ran
+/// between listing and reading the files is reported as an error and the
+/// stale copies are removed.
+fn copy_audit_files(audit_path: &str, dir: &Path) -> Result<(Vec<String>, usize, String), String> {
+    let rotated_prefix = format!("{audit_path}.");
+    let mut names = Vec::new();
+    let mut copies = Vec::new();
+    for file in audit_files(audit_path)? {
+        let name = match file.strip_prefix(&rotated_prefix) {
+            Some(seq) => format!("{AUDIT_FILE}.{seq}"),
+            None => AUDIT_FILE.to_string(),
+        };
+        copies.push((file, name));
+    }
+    if copies.last().is_none_or(|(_, name)| name != AUDIT_FILE) {
+        copies.push((audit_path.to_string(), AUDIT_FILE.to_string()));
+    }
+    let result = copies.iter().try_for_each(|(file, name)| {
+        let bytes = match std::fs::read(file) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound && name == AUDIT_FILE => {
+                Vec::new()
+            }
+            Err(err) => return Err(format!("failed to read {file}: {err}")),
+        };
+        let complete = bytes
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(0, |last| last + 1);
+        let copy = dir.join(name);
+        names.push(name.clone());
+        std::fs::write(&copy, &bytes[..complete])
+            .map_err(|err| format!("failed to write {}: {err}", copy.display()))
+    });
+    match result.and_then(|()| verified_audit_set(dir, &names)) {
+        Ok((records, head)) => Ok((names, records, head)),
+        Err(err) => {
+            for name in &names {
+                let _ = std::fs::remove_file(dir.join(name));
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Copies a directory written by `backup_to_dir` into the configured
+/// `store.sqlite_path`, `audit.jsonl_path` and, when set,
+/// `audit.immutable_mirror_path`. Rotated audit files go back to
+/// `<audit.jsonl_path>.<n>` under the numbers they were backed up with, and
+/// the mirror gets every backed-up record in order. None of the targets may
+/// exist yet. The audit copy is verified against the manifest before
+/// anything is written, and the restored store is opened afterwards so
+/// migrations and the encryption key are checked.
+pub async fn restore_from_dir(cfg: Config, from_dir: &str) -> Result<String, String> {
+    crate::reject_offline_memory_store(&cfg, "POST /v1/admin/store/import")?;
+    if cfg.audit.sink == "stdout" {
+        return Err("audit.sink=stdout has no audit log to restore into".to_string());
+    }
+    let dir = Path::new(from_dir);
+    let manifest_path = dir.join(MANIFEST_FILE);
+    let text = std::fs::read_to_string(&manifest_path)
+        .map_err(|err| format!("failed to read {}: {err}", manifest_path.display()))?;
+    let manifest: BackupManifest = serde_json::from_str(&text)
+        .map_err(|err| format!("invalid manifest {}: {err}", manifest_path.display()))?;
+    if !(1..=BACKUP_FORMAT_VERSION).contains(&manifest.format_version) {
+        return Err(format!(
+            "backup format_version {} is not supported (expected at most {BACKUP_FORMAT_VERSION})",
+            manifest.format_version
+        ));
+    }
+    let mut audit_targets = Vec::new();
+    for name in &manifest.rotated_audit_files {
+        let seq = name
+            .strip_prefix(&format!("{AUDIT_FILE}."))
+            .and_then(|seq| seq.parse::<u64>().ok())
+            .ok_or_else(|| format!("invalid rotated audit file in manifest: {name}"))?;
+        audit_targets.push((name.clone(), format!("{}.{seq}", cfg.audit.jsonl_path)));
+    }
+    audit_targets.push((AUDIT_FILE.to_string(), cfg.audit.jsonl_path.clone()));
+    let names: Vec<String> = audit_targets.iter().map(|(name, _)| name.clone()).collect();
+    let (audit_records, audit_head) = verified_audit_set(dir, &names)?;
+    if (audit_records, audit_head.as_str())
+        != (manifest.audit_records, manifest.audit_head.as_str())
+    {
+        return Err(format!(
+            "audit log in {from_dir} does not match its manifest (records={audit_records}, expected {})",
+            manifest.audit_records
+        ));
+    }
+
+    let sqlite_path = cfg
+        .store
+        .sqlite_path
+        .clone()
+        .ok_or_else(|| "store.sqlite_path is required when store.kind=sqlite".to_string())?;
+    let _store_lock = crate::lock_sqlite_store(&cfg)?;
+    let mut copies = vec![(dir.join(STORE_FILE), sqlite_path)];
+    copies.extend(
+        audit_targets
+            .into_iter()
+            .map(|(name, target)| (dir.join(name), target)),
+    );
+    let mirror = cfg.audit.immutable_mirror_path.clone();
+    let mut targets = copies
+        .iter()
+        .map(|(_, target)| target)
+        .chain(mirror.as_ref());
+    if let Some(target) = targets.find(|target| Path::new(target).exists()) {
+        return Err(format!(
+            "restore target already exists: {target}; move it aside first"
+        ));
+    }
+    for (source, target) in &copies {
+        std::fs::copy(source, target)
+            .map_err(|err| format!("failed to copy {} to {target}: {err}", source.display()))?;
+    }
+    if let Some(mirror) = mirror {
+        let mut records = Vec::new();
+        for name in &names {
+            let path = dir.join(name);
+            let bytes = std::fs::read(&path)
+                .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+            records.extend(bytes);
+        }
+        std::fs::write(&mirror, records)
+            .map_err(|err| format!("failed to write {mirror}: {err}"))?;
+    }
+
+    let state = AppState::new(cfg)?;
+    let stats = state
+        .lock_store()
+        .await
+        .stats()
+        .map_err(|err| format!("restore failed: {err:?}"))?;
+    Ok(format!(
+        "backup restored: {from_dir} (runs={}, archived_runs={}, audit_records={audit_records})",
+        stats.runs, stats.archived_runs
+    ))
+}
+
+/// Verifies the audit files `names` in `dir`, oldest first, and returns
+/// their total record count and the hash of the newest record, empty for an
+/// empty log. Each file's chain is checked against the file before it.
+fn verified_audit_set(dir: &Path, names: &[String]) -> Result<(usize, String), String> {
+    let mut records = 0;
+    let mut head = String::new();
+    for name in names {
+        let (count, last) = verified_audit(&dir.join(name))?;
+        records += count;
+        if count > 0 {
+            head = last;
+        }
+    }
+    Ok((records, head))
+}
+
+/// Verifies the hash chain of `path` and returns its record count and the
+/// hash of its last record, empty for an empty log.
+fn verified_audit(path: &Path) -> Result<(usize, String), String> {
+    let path = path.to_string_lossy();
+    verify_audit_chain(&path).map_err(|err| format!("audit backup {path} is invalid: {err}"))?;
+    let text =
+        std::fs::read_to_string(&*path).map_err(|err| format!("read failed for {path}: {err}"))?;
+    let lines: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let head = match lines.last() {
+        Some(line) => serde_json::from_str::<serde_json::Value>(line)
+            .ok()
+            .and_then(|record| record.get("hash")?.as_str().map(str::to_string))
+            .ok_or_else(|| format!("audit backup {path} has no head hash"))?,
+        None => String::new(),
+    };
+    Ok((lines.len(), head))
+}
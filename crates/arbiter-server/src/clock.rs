@@ -0,0 +1,67 @@
+use arbiter_config::Determinism;
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Source of timestamps and generated identifiers. Live by default; with a
+/// `determinism` config the time is frozen and ids are drawn from a seeded
+/// sequence, so the same requests in the same order yield byte-identical
+/// runs and audit records.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Clock {
+    fixed: Option<Arc<FixedClock>>,
+}
+
+#[derive(Debug)]
+struct FixedClock {
+    now: DateTime<Utc>,
+    seed: u64,
+    issued: AtomicU64,
+}
+
+impl Clock {
+    pub(crate) fn from_config(determinism: Option<&Determinism>) -> Result<Self, String> {
+        let Some(determinism) = determinism else {
+            return Ok(Self::default());
+        };
+        let now = DateTime::parse_from_rfc3339(&determinism.fixed_time)
+            .map_err(|err| format!("invalid determinism.fixed_time: {err}"))?
+            .with_timezone(&Utc);
+        Ok(Self {
+            fixed: Some(Arc::new(FixedClock {
+                now,
+                seed: determinism.seed,
+                issued: AtomicU64::new(0),
+            })),
+        })
+    }
+
+    pub(crate) fn now(&self) -> DateTime<Utc> {
+        match &self.fixed {
+            Some(fixed) => fixed.now,
+            None => Utc::now(),
+        }
+    }
+
+    /// Returns `<prefix>_<32 hex chars>`, the format used for every
+    /// server-generated identifier.
+    pub(crate) fn id(&self, prefix: &str) -> String {
+        let uuid = match &self.fixed {
+            Some(fixed) => {
+                let n = fixed.issued.fetch_add(1, Ordering::SeqCst);
+                let base = fixed.seed.wrapping_add(n.wrapping_mul(2));
+                Uuid::from_u64_pair(splitmix64(base), splitmix64(base.wrapping_add(1)))
+            }
+            None => Uuid::new_v4(),
+        };
+        format!("{prefix}_{}", uuid.simple())
+    }
+}
+
+fn splitmix64(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,25 @@
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::time::Duration;
+
+use crate::errors::{into_error, ApiFailure};
+
+/// Bounds a request by `server.request_timeout`. Only the response is
+/// dropped: store work already handed to `AppState::with_store` keeps the
+/// lock and runs to completion, so a `503 deadline_exceeded` may belong to
+/// a write that committed and was audited.
+pub(crate) async fn enforce_request_deadline(
+    State(timeout): State<Duration>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => into_error(ApiFailure::unavailable(
+            "deadline_exceeded",
+            &format!("request exceeded {}ms deadline", timeout.as_millis()),
+        ))
+        .into_response(),
+    }
+}
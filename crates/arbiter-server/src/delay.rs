@@ -0,0 +1,117 @@
+//! Test-only request delays, enabled by `determinism.request_delays`.
+//! Integration tests use them to force an interleaving of concurrent
+//! requests instead of relying on the scheduler to produce it.
+
+use axum::extract::{Request, State};
+use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use crate::errors::{into_error, ApiFailure};
+
+/// Sleeps this many milliseconds before the request reaches its handler.
+const DELAY_HEADER: &str = "x-arbiter-test-delay-ms";
+/// Holds the store lock this many milliseconds before the request's store
+/// work runs, so other requests queue behind it.
+const STORE_DELAY_HEADER: &str = "x-arbiter-test-store-delay-ms";
+/// Names the request; the name is marked complete once it has responded.
+const MARKER_HEADER: &str = "x-arbiter-test-marker";
+/// Waits until the request carrying this marker has responded.
+const AFTER_HEADER: &str = "x-arbiter-test-after";
+
+tokio::task_local! {
+    static STORE_DELAY: Duration;
+}
+
+/// Markers of completed requests, shared by every request to one app.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RequestDelays {
+    completed: Arc<Mutex<HashSet<String>>>,
+    notify: Arc<Notify>,
+}
+
+impl RequestDelays {
+    async fn wait_for(&self, marker: &str) {
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.completed.lock().unwrap().contains(marker) {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    fn complete(&self, marker: String) {
+        self.completed.lock().unwrap().insert(marker);
+        self.notify.notify_waiters();
+    }
+}
+
+/// How long the current request asked to hold the store lock, if at all.
+pub(crate) fn store_delay() -> Option<Duration> {
+    STORE_DELAY.try_with(|delay| *delay).ok()
+}
+
+/// Applies the `x-arbiter-test-*` headers. It sits inside the request
+/// deadline, so a delay counts against `server.request_timeout`.
+pub(crate) async fn inject_request_delays(
+    State(delays): State<RequestDelays>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let headers = request.headers();
+    let (delay, store_delay) = match (
+        millis_header(headers, DELAY_HEADER),
+        millis_header(headers, STORE_DELAY_HEADER),
+    ) {
+        (Ok(delay), Ok(store_delay)) => (delay, store_delay),
+        (Err(err), _) | (_, Err(err)) => return into_error(err).into_response(),
+    };
+    let marker = text_header(headers, MARKER_HEADER);
+    let after = text_header(headers, AFTER_HEADER);
+
+    if let Some(after) = after {
+        delays.wait_for(&after).await;
+    }
+    if let Some(delay) = delay {
+        tokio::time::sleep(delay).await;
+    }
+    let response = match store_delay {
+        Some(store_delay) => STORE_DELAY.scope(store_delay, next.run(request)).await,
+        None => next.run(request).await,
+    };
+    if let Some(marker) = marker {
+        delays.complete(marker);
+    }
+    response
+}
+
+fn millis_header(headers: &HeaderMap, name: &str) -> Result<Option<Duration>, ApiFailure> {
+    let Some(value) = headers.get(name) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(|millis| Some(Duration::from_millis(millis)))
+        .ok_or_else(|| {
+            ApiFailure::bad_request(
+                "invalid_request",
+                &format!("{name} must be a number of milliseconds"),
+            )
+        })
+}
+
+fn text_header(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
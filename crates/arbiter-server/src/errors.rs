@@ -1,4 +1,5 @@
 use arbiter_contracts::{ErrorBody, ErrorResponse};
+use arbiter_store::StoreError;
 use axum::http::StatusCode;
 use axum::Json;
 
@@ -56,6 +57,15 @@ impl ApiFailure {
         }
     }
 
+    pub(crate) fn unavailable(code: &str, message: &str) -> Self {
+        Self {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            code: code.to_string(),
+            message: message.to_string(),
+            details: None,
+        }
+    }
+
     pub(crate) fn internal(message: &str) -> Self {
         Self {
             status: StatusCode::INTERNAL_SERVER_ERROR,
@@ -64,6 +74,22 @@ impl ApiFailure {
             details: None,
         }
     }
+
+    pub(crate) fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+impl From<StoreError> for ApiFailure {
+    fn from(err: StoreError) -> Self {
+        match err {
+            StoreError::Conflict(message) | StoreError::Unsupported(message) => {
+                Self::conflict("conflict", &message)
+            }
+            StoreError::Backend(message) => Self::internal(&message),
+        }
+    }
 }
 
 pub(crate) type ApiErrorResponse = (StatusCode, Json<ErrorResponse>);
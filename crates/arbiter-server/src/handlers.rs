@@ -1,30 +1,56 @@
 use arbiter_contracts::{
-    Approval, ApprovalActionRequest, ApprovalStatus, AuditRunEventsResponse, ContractsMetadata,
-    Decision, DecisionEffect, ExecutionPermit, OperationRequest, OperationRequestAccepted, Run,
-    RunEnvelope, RunStatus, Step, StepIntent, StepResultResponse, StepResultSubmission, StepStatus,
+    Approval, ApprovalActionRequest, ApprovalContext, ApprovalStatus, AuditCheckpointsResponse,
+    AuditInclusionProof, AuditRunEventsResponse, ComponentHealth, ContractsMetadata, Decision,
+    DecisionEffect, ExecutionPermit, OperationRequest, OperationRequestAccepted,
+    PolicyCheckRequest, PolicyCheckResponse, ReadinessReport, Run, RunEnvelope, RunStatus, Step,
+    StepIntent, StepResultResponse, StepResultSubmission, StepStatus,
 };
-use axum::extract::{Path as AxPath, State};
+use axum::extract::{Path as AxPath, Query, State};
 use axum::http::StatusCode;
 use axum::Json;
-use chrono::{Duration, Utc};
 use serde::de::DeserializeOwned;
+use serde::Deserialize;
 use serde_json::json;
-use uuid::Uuid;
+use std::time::Duration;
 
-use arbiter_kernel::jcs_sha256_hex;
-use arbiter_kernel::policy::{evaluate, resolve_approvers, PolicyInput};
+use arbiter_kernel::ids::validate_identifier;
+use arbiter_kernel::policy::{
+    evaluate, resolve_approvers, ApproverResolverConfig, PolicyConfig, PolicyDecision, PolicyInput,
+};
 use arbiter_kernel::state_machine::{
     can_transition_approval, can_transition_run, can_transition_step,
 };
+use arbiter_kernel::{jcs_canonical, jcs_sha256_hex};
 
-use crate::audit::{list_run_events, AuditRecord};
+use crate::audit::{inclusion_proof, list_checkpoints, list_run_events, AuditRecord};
+use crate::clock::Clock;
 use crate::errors::{into_error, ApiErrorResponse, ApiFailure};
-use crate::store::AppState;
+use crate::store::{to_chrono, AppState, RequestPayload, StoreBackend};
 
 pub(crate) async fn healthz() -> (StatusCode, &'static str) {
     (StatusCode::OK, "ok")
 }
 
+/// Unlike `healthz`, waits for the store lock and touches the backend and
+/// audit files, so a wedged or unwritable dependency shows up as `503`.
+pub(crate) async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<ReadinessReport>) {
+    let components = match state.with_store(|store| store.readiness()).await {
+        Ok(components) => components,
+        Err(err) => vec![ComponentHealth {
+            name: "store".to_string(),
+            ready: false,
+            detail: Some(format!("{err:?}")),
+        }],
+    };
+    let ready = components.iter().all(|component| component.ready);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(ReadinessReport { ready, components }))
+}
+
 pub(crate) async fn get_contracts(State(state): State<AppState>) -> Json<ContractsMetadata> {
     Json(state.contracts_metadata())
 }
@@ -33,14 +59,30 @@ pub(crate) async fn create_operation_request(
     State(state): State<AppState>,
     Json(input): Json<OperationRequest>,
 ) -> Result<(StatusCode, Json<OperationRequestAccepted>), ApiErrorResponse> {
-    let payload_hash = payload_hash(&input)?;
+    check_identifier("request_id", &input.request_id)?;
+    let clock = state.clock().clone();
+    let payload = request_payload(&input)?;
     let idem_key = format!("operation_request:{}", input.request_id);
 
-    let mut store = state.lock_store().await;
-    if let Some(idem) = store.get_idempotency(&idem_key).map_err(into_error)? {
-        if idem.payload_hash == payload_hash {
-            let response: OperationRequestAccepted = decode_snapshot(&idem.response_json)?;
-            return Ok((StatusCode::CREATED, Json(response)));
+    let response = state
+        .with_store(move |store| {
+            record_operation_request(store, &clock, input, &idem_key, &payload)
+        })
+        .await
+        .map_err(into_error)??;
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+fn record_operation_request(
+    store: &mut StoreBackend,
+    clock: &Clock,
+    input: OperationRequest,
+    idem_key: &str,
+    payload: &RequestPayload,
+) -> Result<OperationRequestAccepted, ApiErrorResponse> {
+    if let Some(idem) = store.get_idempotency(idem_key).map_err(into_error)? {
+        if idem.payload_hash == payload.hash {
+            return decode_snapshot(&idem.response_json);
         }
         return Err(into_error(ApiFailure::conflict(
             "conflict",
@@ -59,8 +101,8 @@ pub(crate) async fn create_operation_request(
         )));
     }
 
-    let run_id = format!("run_{}", Uuid::new_v4().simple());
-    let now = Utc::now().to_rfc3339();
+    let run_id = clock.id("run");
+    let now = clock.now().to_rfc3339();
     let run = Run {
         run_id: run_id.clone(),
         request_id: input.request_id,
@@ -99,8 +141,8 @@ pub(crate) async fn create_operation_request(
 
     store
         .put_idempotency(
-            &idem_key,
-            &payload_hash,
+            idem_key,
+            payload,
             &serde_json::to_string(&response)
                 .map_err(|err| into_error(ApiFailure::internal(&err.to_string())))?,
         )
@@ -115,18 +157,30 @@ pub(crate) async fn create_operation_request(
         ))
         .map_err(into_error)?;
 
-    Ok((StatusCode::CREATED, Json(response)))
+    Ok(response)
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct GetRunQuery {
+    #[serde(default)]
+    include_archived: bool,
 }
 
 pub(crate) async fn get_run(
     State(state): State<AppState>,
     AxPath(run_id): AxPath<String>,
+    Query(query): Query<GetRunQuery>,
 ) -> Result<Json<RunEnvelope>, ApiErrorResponse> {
-    let store = state.lock_store().await;
-    let run = store
-        .get_run(&run_id)
-        .map_err(into_error)?
-        .ok_or_else(|| ApiFailure::not_found("not_found", "run not found"))
+    let run = state
+        .with_store(move |store| {
+            let mut run = store.get_run(&run_id)?;
+            if run.is_none() && query.include_archived {
+                run = store.get_archived_run(&run_id)?;
+            }
+            run.ok_or_else(|| ApiFailure::not_found("not_found", "run not found"))
+        })
+        .await
+        .and_then(|run| run)
         .map_err(into_error)?;
     Ok(Json(run))
 }
@@ -136,6 +190,14 @@ pub(crate) async fn submit_step_intent(
     AxPath(run_id): AxPath<String>,
     Json(intent): Json<StepIntent>,
 ) -> Result<Json<Step>, ApiErrorResponse> {
+    check_identifier("run_id", &run_id)?;
+    if let Some(step_id) = &intent.step_id {
+        check_identifier("step_id", step_id)?;
+    }
+    if let Some(client_step_id) = &intent.client_step_id {
+        check_identifier("client_step_id", client_step_id)?;
+    }
+    let clock = state.clock().clone();
     let id_component = intent
         .client_step_id
         .clone()
@@ -147,17 +209,60 @@ pub(crate) async fn submit_step_intent(
             ))
         })?;
     let idem_key = format!("step_intent:{run_id}:{id_component}");
-    let payload_hash = payload_hash(&intent)?;
+    let payload = request_payload(&intent)?;
 
     let policy_cfg = state.policy_config().clone();
     let approver_cfg = state.approver_config().clone();
-    let permit_ttl = state.permit_ttl_seconds();
+    let permit_ttl = state.permit_ttl();
+
+    let step = state
+        .with_store(move |store| {
+            declare_step(
+                store,
+                &clock,
+                StepDeclaration {
+                    run_id,
+                    intent,
+                    idem_key,
+                    payload,
+                    policy_cfg,
+                    approver_cfg,
+                    permit_ttl,
+                },
+            )
+        })
+        .await
+        .map_err(into_error)??;
+    Ok(Json(step))
+}
+
+struct StepDeclaration {
+    run_id: String,
+    intent: StepIntent,
+    idem_key: String,
+    payload: RequestPayload,
+    policy_cfg: PolicyConfig,
+    approver_cfg: ApproverResolverConfig,
+    permit_ttl: Duration,
+}
 
-    let mut store = state.lock_store().await;
+fn declare_step(
+    store: &mut StoreBackend,
+    clock: &Clock,
+    input: StepDeclaration,
+) -> Result<Step, ApiErrorResponse> {
+    let StepDeclaration {
+        run_id,
+        intent,
+        idem_key,
+        payload,
+        policy_cfg,
+        approver_cfg,
+        permit_ttl,
+    } = input;
     if let Some(idem) = store.get_idempotency(&idem_key).map_err(into_error)? {
-        if idem.payload_hash == payload_hash {
-            let response: Step = decode_snapshot(&idem.response_json)?;
-            return Ok(Json(response));
+        if idem.payload_hash == payload.hash {
+            return decode_snapshot(&idem.response_json);
         }
         return Err(into_error(ApiFailure::conflict(
             "conflict",
@@ -173,17 +278,14 @@ pub(crate) async fn submit_step_intent(
 
     transition_run(&mut run.run.status, RunStatus::Planning)?;
 
-    let step_id = intent
-        .step_id
-        .clone()
-        .unwrap_or_else(|| format!("step_{}", Uuid::new_v4().simple()));
+    let step_id = intent.step_id.clone().unwrap_or_else(|| clock.id("step"));
     let mut step = Step {
         step_id: step_id.clone(),
         run_id: run_id.clone(),
         status: StepStatus::Declared,
         intent: intent.clone(),
         decision: Decision {
-            decision_id: format!("dec_{}", Uuid::new_v4().simple()),
+            decision_id: clock.id("dec"),
             effect: DecisionEffect::Allow,
             rationale: "pending evaluation".to_string(),
             applied_policies: vec![],
@@ -192,7 +294,7 @@ pub(crate) async fn submit_step_intent(
         },
         permit: None,
         approval_id: None,
-        created_at: Utc::now().to_rfc3339(),
+        created_at: clock.now().to_rfc3339(),
         updated_at: None,
     };
     transition_step(&mut step.status, StepStatus::Evaluating)?;
@@ -211,7 +313,7 @@ pub(crate) async fn submit_step_intent(
         approvers,
     );
     step.decision = Decision {
-        decision_id: format!("dec_{}", Uuid::new_v4().simple()),
+        decision_id: clock.id("dec"),
         effect: policy.effect.clone(),
         rationale: policy.rationale,
         applied_policies: policy.applied_policies,
@@ -227,7 +329,7 @@ pub(crate) async fn submit_step_intent(
         DecisionEffect::RequireApproval => {
             transition_step(&mut step.status, StepStatus::ApprovalRequired)?;
             transition_run(&mut run.run.status, RunStatus::WaitingForApproval)?;
-            let approval_id = format!("apr_{}", Uuid::new_v4().simple());
+            let approval_id = clock.id("apr");
             let approval = Approval {
                 approval_id: approval_id.clone(),
                 run_id: run_id.clone(),
@@ -235,9 +337,16 @@ pub(crate) async fn submit_step_intent(
                 status: ApprovalStatus::Requested,
                 required_approvers: step.decision.required_approvers.clone(),
                 reason: step.decision.rationale.clone(),
-                created_at: Utc::now().to_rfc3339(),
+                created_at: clock.now().to_rfc3339(),
                 decided_at: None,
                 decided_by: None,
+                context: Some(ApprovalContext {
+                    objective: run.run.objective.clone(),
+                    requester: run.run.requester.clone(),
+                    environment: run.run.environment.clone(),
+                    intent: step.intent.clone(),
+                    applied_policies: step.decision.applied_policies.clone(),
+                }),
             };
             step.approval_id = Some(approval_id.clone());
             run.approvals.push(approval);
@@ -249,6 +358,7 @@ pub(crate) async fn submit_step_intent(
             transition_step(&mut step.status, StepStatus::Permitted)?;
             transition_run(&mut run.run.status, RunStatus::Ready)?;
             let permit = issue_permit(
+                clock,
                 &run_id,
                 &step_id,
                 permit_ttl,
@@ -259,15 +369,15 @@ pub(crate) async fn submit_step_intent(
         }
     }
 
-    step.updated_at = Some(Utc::now().to_rfc3339());
+    step.updated_at = Some(clock.now().to_rfc3339());
     run.steps.push(step.clone());
-    run.run.updated_at = Utc::now().to_rfc3339();
+    run.run.updated_at = clock.now().to_rfc3339();
     store.put_run(run).map_err(into_error)?;
 
     store
         .put_idempotency(
             &idem_key,
-            &payload_hash,
+            &payload,
             &serde_json::to_string(&step)
                 .map_err(|err| into_error(ApiFailure::internal(&err.to_string())))?,
         )
@@ -284,7 +394,45 @@ pub(crate) async fn submit_step_intent(
     audit.policy_refs = step.decision.applied_policies.clone();
     store.append_audit(audit).map_err(into_error)?;
 
-    Ok(Json(step))
+    Ok(step)
+}
+
+pub(crate) async fn check_policy(
+    State(state): State<AppState>,
+    Json(input): Json<PolicyCheckRequest>,
+) -> Json<PolicyCheckResponse> {
+    let policy = evaluate_check(&input, state.policy_config(), state.approver_config());
+    Json(PolicyCheckResponse {
+        effect: policy.effect,
+        rationale: policy.rationale,
+        applied_policies: policy.applied_policies,
+        permit_constraints: policy.permit_constraints,
+        required_approvers: policy.required_approvers,
+        environment: input.environment,
+        intent: input.intent,
+    })
+}
+
+/// Evaluates a policy check without touching the store; shared by
+/// `POST /v1/policy/check` and `arbiter policy-diff`.
+pub(crate) fn evaluate_check(
+    input: &PolicyCheckRequest,
+    policy_cfg: &PolicyConfig,
+    approver_cfg: &ApproverResolverConfig,
+) -> PolicyDecision {
+    let approvers = resolve_approvers(&input.environment, approver_cfg);
+    evaluate(
+        &PolicyInput {
+            provider: input.intent.provider.clone(),
+            capability: input.intent.capability.clone(),
+            intent_type: input.intent.intent_type.clone(),
+            risk_level: input.intent.risk_level.clone(),
+            metadata: input.intent.metadata.clone(),
+        },
+        &input.environment,
+        policy_cfg,
+        approvers,
+    )
 }
 
 pub(crate) async fn grant_approval(
@@ -316,14 +464,32 @@ pub(crate) async fn submit_step_result(
     AxPath(run_id): AxPath<String>,
     Json(input): Json<StepResultSubmission>,
 ) -> Result<Json<StepResultResponse>, ApiErrorResponse> {
+    check_identifier("run_id", &run_id)?;
+    check_identifier("step_id", &input.step_id)?;
+    let clock = state.clock().clone();
     let idem_key = format!("step_result:{run_id}:{}", input.step_id);
-    let payload_hash = payload_hash(&input)?;
+    let payload = request_payload(&input)?;
 
-    let mut store = state.lock_store().await;
-    if let Some(idem) = store.get_idempotency(&idem_key).map_err(into_error)? {
-        if idem.payload_hash == payload_hash {
-            let response: StepResultResponse = decode_snapshot(&idem.response_json)?;
-            return Ok(Json(response));
+    let response = state
+        .with_store(move |store| {
+            record_step_result(store, &clock, &run_id, input, &idem_key, &payload)
+        })
+        .await
+        .map_err(into_error)??;
+    Ok(Json(response))
+}
+
+fn record_step_result(
+    store: &mut StoreBackend,
+    clock: &Clock,
+    run_id: &str,
+    input: StepResultSubmission,
+    idem_key: &str,
+    payload: &RequestPayload,
+) -> Result<StepResultResponse, ApiErrorResponse> {
+    if let Some(idem) = store.get_idempotency(idem_key).map_err(into_error)? {
+        if idem.payload_hash == payload.hash {
+            return decode_snapshot(&idem.response_json);
         }
         return Err(into_error(ApiFailure::conflict(
             "conflict",
@@ -332,7 +498,7 @@ pub(crate) async fn submit_step_result(
     }
 
     let mut run = store
-        .get_run(&run_id)
+        .get_run(run_id)
         .map_err(into_error)?
         .ok_or_else(|| ApiFailure::not_found("not_found", "run not found"))
         .map_err(into_error)?;
@@ -367,8 +533,8 @@ pub(crate) async fn submit_step_result(
         transition_run(&mut run.run.status, RunStatus::Succeeded)?;
     }
 
-    step.updated_at = Some(Utc::now().to_rfc3339());
-    run.run.updated_at = Utc::now().to_rfc3339();
+    step.updated_at = Some(clock.now().to_rfc3339());
+    run.run.updated_at = clock.now().to_rfc3339();
     let response = StepResultResponse {
         step_status: step.status.clone(),
         run_status: run.run.status.clone(),
@@ -377,8 +543,8 @@ pub(crate) async fn submit_step_result(
     store.put_run(run).map_err(into_error)?;
     store
         .put_idempotency(
-            &idem_key,
-            &payload_hash,
+            idem_key,
+            payload,
             &serde_json::to_string(&response)
                 .map_err(|err| into_error(ApiFailure::internal(&err.to_string())))?,
         )
@@ -386,31 +552,101 @@ pub(crate) async fn submit_step_result(
 
     let mut audit = AuditRecord::new(
         "step_result_recorded",
-        &run_id,
+        run_id,
         "executor",
         json!({"step_id": input.step_id, "execution_result": input.execution_result}),
     );
     audit.step_id = Some(input.step_id);
     store.append_audit(audit).map_err(into_error)?;
 
-    Ok(Json(response))
+    Ok(response)
 }
 
 pub(crate) async fn get_run_audit(
     State(state): State<AppState>,
     AxPath(run_id): AxPath<String>,
 ) -> Result<Json<AuditRunEventsResponse>, ApiErrorResponse> {
-    let store = state.lock_store().await;
-    let payload = list_run_events(store.audit_path(), &run_id).map_err(into_error)?;
+    let payload = state
+        .with_store(move |store| {
+            store
+                .audit_log()
+                .and_then(|path| list_run_events(path, &run_id))
+        })
+        .await
+        .and_then(|payload| payload)
+        .map_err(into_error)?;
     Ok(Json(payload))
 }
 
+pub(crate) async fn get_audit_checkpoints(
+    State(state): State<AppState>,
+) -> Result<Json<AuditCheckpointsResponse>, ApiErrorResponse> {
+    let interval = state.audit_checkpoint_interval();
+    let payload = state
+        .with_store(move |store| {
+            store
+                .audit_log()
+                .and_then(|path| list_checkpoints(path, interval))
+        })
+        .await
+        .and_then(|payload| payload)
+        .map_err(into_error)?;
+    Ok(Json(payload))
+}
+
+pub(crate) async fn get_audit_proof(
+    State(state): State<AppState>,
+    AxPath(audit_id): AxPath<String>,
+) -> Result<Json<AuditInclusionProof>, ApiErrorResponse> {
+    let interval = state.audit_checkpoint_interval();
+    let proof = state
+        .with_store(move |store| {
+            store
+                .audit_log()
+                .and_then(|path| inclusion_proof(path, &audit_id, interval))
+        })
+        .await
+        .and_then(|proof| proof)
+        .map_err(into_error)?
+        .ok_or_else(|| ApiFailure::not_found("not_found", "audit event not found"))
+        .map_err(into_error)?;
+    Ok(Json(proof))
+}
+
+pub(crate) async fn get_approval(
+    State(state): State<AppState>,
+    AxPath(approval_id): AxPath<String>,
+) -> Result<Json<Approval>, ApiErrorResponse> {
+    let approval = state
+        .with_store(move |store| {
+            let run_id = store
+                .run_id_for_approval(&approval_id)?
+                .ok_or_else(|| ApiFailure::not_found("not_found", "approval not found"))?;
+            let run = match store.get_run(&run_id)? {
+                Some(run) => run,
+                None => store
+                    .get_archived_run(&run_id)?
+                    .ok_or_else(|| ApiFailure::not_found("not_found", "run not found"))?,
+            };
+            run.approvals
+                .into_iter()
+                .find(|a| a.approval_id == approval_id)
+                .ok_or_else(|| ApiFailure::not_found("not_found", "approval not found"))
+        })
+        .await
+        .and_then(|approval| approval)
+        .map_err(into_error)?;
+    Ok(Json(approval))
+}
+
 async fn apply_approval_action(
     state: AppState,
     approval_id: String,
     input: ApprovalActionRequest,
     target: ApprovalStatus,
 ) -> Result<Json<Approval>, ApiErrorResponse> {
+    check_identifier("approval_id", &approval_id)?;
+    let clock = state.clock().clone();
     let idem_key = format!(
         "approval_action:{approval_id}:{}",
         match target {
@@ -420,14 +656,54 @@ async fn apply_approval_action(
             ApprovalStatus::Requested => "requested",
         }
     );
-    let payload_hash = payload_hash(&input)?;
-    let permit_ttl = state.permit_ttl_seconds();
+    let payload = request_payload(&input)?;
+    let permit_ttl = state.permit_ttl();
+
+    let approval = state
+        .with_store(move |store| {
+            decide_approval(
+                store,
+                &clock,
+                ApprovalDecision {
+                    approval_id,
+                    input,
+                    target,
+                    idem_key,
+                    payload,
+                    permit_ttl,
+                },
+            )
+        })
+        .await
+        .map_err(into_error)??;
+    Ok(Json(approval))
+}
+
+struct ApprovalDecision {
+    approval_id: String,
+    input: ApprovalActionRequest,
+    target: ApprovalStatus,
+    idem_key: String,
+    payload: RequestPayload,
+    permit_ttl: Duration,
+}
 
-    let mut store = state.lock_store().await;
+fn decide_approval(
+    store: &mut StoreBackend,
+    clock: &Clock,
+    decision: ApprovalDecision,
+) -> Result<Approval, ApiErrorResponse> {
+    let ApprovalDecision {
+        approval_id,
+        input,
+        target,
+        idem_key,
+        payload,
+        permit_ttl,
+    } = decision;
     if let Some(idem) = store.get_idempotency(&idem_key).map_err(into_error)? {
-        if idem.payload_hash == payload_hash {
-            let response: Approval = decode_snapshot(&idem.response_json)?;
-            return Ok(Json(response));
+        if idem.payload_hash == payload.hash {
+            return decode_snapshot(&idem.response_json);
         }
         return Err(into_error(ApiFailure::conflict(
             "conflict",
@@ -457,12 +733,12 @@ async fn apply_approval_action(
         store
             .put_idempotency(
                 &idem_key,
-                &payload_hash,
+                &payload,
                 &serde_json::to_string(&snapshot)
                     .map_err(|err| into_error(ApiFailure::internal(&err.to_string())))?,
             )
             .map_err(into_error)?;
-        return Ok(Json(snapshot));
+        return Ok(snapshot);
     }
     if !can_transition_approval(&approval.status, &target) {
         let reason = match approval.status {
@@ -475,7 +751,7 @@ async fn apply_approval_action(
     }
 
     approval.status = target.clone();
-    approval.decided_at = Some(Utc::now().to_rfc3339());
+    approval.decided_at = Some(clock.now().to_rfc3339());
     approval.decided_by = Some(input.actor.clone());
 
     let step = run
@@ -489,6 +765,7 @@ async fn apply_approval_action(
             transition_step(&mut step.status, StepStatus::Permitted)?;
             transition_run(&mut run.run.status, RunStatus::Ready)?;
             let permit = issue_permit(
+                clock,
                 &run_id,
                 &step.step_id,
                 permit_ttl,
@@ -508,13 +785,13 @@ async fn apply_approval_action(
         ApprovalStatus::Requested => {}
     }
 
-    run.run.updated_at = Utc::now().to_rfc3339();
+    run.run.updated_at = clock.now().to_rfc3339();
     let snapshot = approval.clone();
     store.put_run(run).map_err(into_error)?;
     store
         .put_idempotency(
             &idem_key,
-            &payload_hash,
+            &payload,
             &serde_json::to_string(&snapshot)
                 .map_err(|err| into_error(ApiFailure::internal(&err.to_string())))?,
         )
@@ -531,35 +808,50 @@ async fn apply_approval_action(
     audit.rationale = input.reason;
     store.append_audit(audit).map_err(into_error)?;
 
-    Ok(Json(snapshot))
+    Ok(snapshot)
 }
 
 fn issue_permit(
+    clock: &Clock,
     run_id: &str,
     step_id: &str,
-    ttl_seconds: u64,
+    ttl: Duration,
     constraints: serde_json::Value,
 ) -> ExecutionPermit {
-    let issued = Utc::now();
+    let issued = clock.now();
     ExecutionPermit {
-        permit_id: format!("permit_{}", Uuid::new_v4().simple()),
+        permit_id: clock.id("permit"),
         run_id: run_id.to_string(),
         step_id: step_id.to_string(),
         issuer: "arbiter".to_string(),
         constraints,
         issued_at: issued.to_rfc3339(),
-        expires_at: (issued + Duration::seconds(ttl_seconds as i64)).to_rfc3339(),
+        expires_at: (issued + to_chrono(ttl)).to_rfc3339(),
     }
 }
 
-fn payload_hash<T: serde::Serialize>(payload: &T) -> Result<String, ApiErrorResponse> {
+fn check_identifier(field: &str, value: &str) -> Result<(), ApiErrorResponse> {
+    validate_identifier(field, value)
+        .map_err(|msg| into_error(ApiFailure::bad_request("invalid_identifier", &msg)))
+}
+
+fn request_payload<T: serde::Serialize>(payload: &T) -> Result<RequestPayload, ApiErrorResponse> {
     let value = serde_json::to_value(payload).map_err(|err| {
         into_error(ApiFailure::internal(&format!(
             "payload encode failed: {err}"
         )))
     })?;
-    jcs_sha256_hex(&value)
-        .map_err(|err| into_error(ApiFailure::internal(&format!("payload hash failed: {err}"))))
+    let hash = jcs_sha256_hex(&value)
+        .map_err(|err| into_error(ApiFailure::internal(&format!("payload hash failed: {err}"))))?;
+    let canonical_json = jcs_canonical(&value).map_err(|err| {
+        into_error(ApiFailure::internal(&format!(
+            "payload encode failed: {err}"
+        )))
+    })?;
+    Ok(RequestPayload {
+        hash,
+        canonical_json,
+    })
 }
 
 fn decode_snapshot<T: DeserializeOwned>(input: &str) -> Result<T, ApiErrorResponse> {
@@ -0,0 +1,244 @@
+use arbiter_config::StartupCheck;
+use arbiter_contracts::{ApprovalStatus, RunStatus};
+use arbiter_kernel::state_machine::is_terminal_run;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::audit::{verify_audit_tail, AuditRecord};
+use crate::errors::ApiFailure;
+use crate::store::{AppState, StoreBackend};
+
+#[derive(Debug, Clone)]
+pub(crate) struct IntegrityFinding {
+    pub run_id: Option<String>,
+    pub issue: String,
+    pub repaired: bool,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct IntegrityReport {
+    pub scanned_runs: usize,
+    pub findings: Vec<IntegrityFinding>,
+}
+
+impl IntegrityFinding {
+    fn new(run_id: Option<&str>, issue: String) -> Self {
+        Self {
+            run_id: run_id.map(|v| v.to_string()),
+            issue,
+            repaired: false,
+        }
+    }
+}
+
+/// Cross-checks run envelopes against the approval mapping and run status
+/// invariants. Only mapping drift is repairable; every repair is audited.
+pub(crate) fn check_store(
+    store: &mut StoreBackend,
+    repair: bool,
+) -> Result<IntegrityReport, ApiFailure> {
+    let mut findings = Vec::new();
+
+    if let (Some(version), Some(supported)) =
+        (store.schema_version(), store.supported_schema_version())
+    {
+        if version != supported {
+            findings.push(IntegrityFinding::new(
+                None,
+                format!(
+                    "store schema version {version} does not match supported version {supported}"
+                ),
+            ));
+        }
+    }
+
+    let runs = store.list_runs()?;
+    let mappings: BTreeMap<String, String> = store.list_approval_mappings()?.into_iter().collect();
+
+    for (approval_id, run_id) in &mappings {
+        let run = match runs.iter().find(|r| &r.run.run_id == run_id) {
+            Some(run) => Some(run.clone()),
+            None => store.get_archived_run(run_id)?,
+        };
+        let owned = run
+            .map(|r| r.approvals.iter().any(|a| &a.approval_id == approval_id))
+            .unwrap_or(false);
+        if owned {
+            continue;
+        }
+        let mut finding = IntegrityFinding::new(
+            Some(run_id),
+            format!("approval {approval_id} is mapped to run {run_id} which does not contain it"),
+        );
+        if repair {
+            store.remove_approval_mapping(approval_id)?;
+            audit_repair(store, run_id, &finding.issue)?;
+            finding.repaired = true;
+        }
+        findings.push(finding);
+    }
+
+    for run in &runs {
+        let run_id = run.run.run_id.as_str();
+        for approval in &run.approvals {
+            if mappings.get(&approval.approval_id).map(String::as_str) == Some(run_id) {
+                continue;
+            }
+            let mut finding = IntegrityFinding::new(
+                Some(run_id),
+                format!(
+                    "approval {} has no mapping to its run",
+                    approval.approval_id
+                ),
+            );
+            if repair {
+                store.map_approval_to_run(&approval.approval_id, run_id)?;
+                audit_repair(store, run_id, &finding.issue)?;
+                finding.repaired = true;
+            }
+            findings.push(finding);
+        }
+
+        if run.run.status == RunStatus::WaitingForApproval
+            && !run
+                .approvals
+                .iter()
+                .any(|a| a.status == ApprovalStatus::Requested)
+        {
+            findings.push(IntegrityFinding::new(
+                Some(run_id),
+                "run is waiting_for_approval without a requested approval".to_string(),
+            ));
+        }
+    }
+
+    Ok(IntegrityReport {
+        scanned_runs: runs.len(),
+        findings,
+    })
+}
+
+/// Counts behind an `arbiter store-fsck` report.
+#[derive(Debug, Clone)]
+pub(crate) struct FsckReport {
+    pub integrity: IntegrityReport,
+    pub archived_runs: usize,
+    pub idempotency_records: usize,
+}
+
+/// `check_store` plus the checks too slow for every startup: archived runs
+/// against hot runs, and every idempotency record against the run or
+/// approval its key names. Only what `check_store` repairs is repairable.
+pub(crate) fn fsck_store(store: &mut StoreBackend, repair: bool) -> Result<FsckReport, ApiFailure> {
+    let mut integrity = check_store(store, repair)?;
+    let runs = store.list_runs()?;
+    let archived = store.list_archived_runs()?;
+    let idempotency = store.list_idempotency()?;
+    let findings = &mut integrity.findings;
+
+    let hot_ids: BTreeSet<&str> = runs.iter().map(|r| r.run.run_id.as_str()).collect();
+    for archived in &archived {
+        let run = &archived.envelope.run;
+        if hot_ids.contains(run.run_id.as_str()) {
+            findings.push(IntegrityFinding::new(
+                Some(&run.run_id),
+                "run is both hot and archived".to_string(),
+            ));
+        }
+        if !is_terminal_run(&run.status) {
+            findings.push(IntegrityFinding::new(
+                Some(&run.run_id),
+                "archived run is not in a terminal status".to_string(),
+            ));
+        }
+    }
+
+    let all_runs: Vec<_> = runs
+        .iter()
+        .chain(archived.iter().map(|a| &a.envelope))
+        .collect();
+    let mut runs_by_request: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for run in &all_runs {
+        runs_by_request
+            .entry(run.run.request_id.as_str())
+            .or_default()
+            .insert(run.run.run_id.as_str());
+    }
+    for (request_id, run_ids) in &runs_by_request {
+        if run_ids.len() > 1 {
+            let run_ids: Vec<&str> = run_ids.iter().copied().collect();
+            findings.push(IntegrityFinding::new(
+                None,
+                format!(
+                    "request_id {request_id} is shared by runs {}",
+                    run_ids.join(", ")
+                ),
+            ));
+        }
+    }
+
+    let run_ids: BTreeSet<&str> = all_runs.iter().map(|r| r.run.run_id.as_str()).collect();
+    let approval_ids: BTreeSet<&str> = all_runs
+        .iter()
+        .flat_map(|r| r.approvals.iter().map(|a| a.approval_id.as_str()))
+        .collect();
+    for (key, _) in &idempotency {
+        let (kind, rest) = key.split_once(':').unwrap_or((key.as_str(), ""));
+        let target = rest.split(':').next().unwrap_or_default();
+        let issue = match kind {
+            "operation_request" if !runs_by_request.contains_key(rest) => {
+                format!("idempotency record {key} has no run with request_id {rest}")
+            }
+            "step_intent" | "step_result" if !run_ids.contains(target) => {
+                format!("idempotency record {key} names missing run {target}")
+            }
+            "approval_action" if !approval_ids.contains(target) => {
+                format!("idempotency record {key} names missing approval {target}")
+            }
+            "operation_request" | "step_intent" | "step_result" | "approval_action" => continue,
+            _ => format!("idempotency record {key} has an unrecognised key"),
+        };
+        findings.push(IntegrityFinding::new(None, issue));
+    }
+
+    Ok(FsckReport {
+        integrity,
+        archived_runs: archived.len(),
+        idempotency_records: idempotency.len(),
+    })
+}
+
+pub(crate) async fn run_startup_check(
+    state: &AppState,
+    check: &StartupCheck,
+) -> Result<(), String> {
+    let mut store = state.lock_store().await;
+
+    if let Some(path) = store.audit_path() {
+        verify_audit_tail(path, check.audit_tail_records as usize)
+            .map_err(|err| format!("startup check failed: audit tail: {err}"))?;
+    }
+
+    let repair = check.on_violation == "repair";
+    let report =
+        check_store(&mut store, repair).map_err(|err| format!("startup check failed: {err:?}"))?;
+    let unresolved: Vec<String> = report
+        .findings
+        .into_iter()
+        .filter(|f| !f.repaired)
+        .map(|f| match f.run_id {
+            Some(run_id) => format!("{run_id}: {}", f.issue),
+            None => f.issue,
+        })
+        .collect();
+    if !unresolved.is_empty() {
+        return Err(format!("startup check failed: {}", unresolved.join("; ")));
+    }
+    Ok(())
+}
+
+fn audit_repair(store: &mut StoreBackend, run_id: &str, issue: &str) -> Result<(), ApiFailure> {
+    let mut audit = AuditRecord::new("store_repaired", run_id, "arbiter", json!({"issue": issue}));
+    audit.rationale = Some(issue.to_string());
+    store.append_audit(audit)
+}
@@ -1,57 +1,464 @@
+mod admin;
 mod audit;
+mod backup;
+mod clock;
 mod contracts;
+mod deadline;
+mod delay;
 mod errors;
 mod handlers;
+mod integrity;
+mod listener;
+mod lookup;
+mod maintenance;
+mod policy_diff;
+mod policy_test;
+mod recovery;
+mod s3;
+mod startup;
 mod store;
+mod tasks;
 
 use arbiter_config::Config;
+use arbiter_contracts::{StartupReport, StoreSnapshot};
+use arbiter_store::Store;
+use axum::middleware::from_fn_with_state;
 use axum::routing::{get, post};
 use axum::Router;
-use std::net::SocketAddr;
 
+use crate::admin::{
+    backup_store, get_idempotency_entry, get_panics, get_read_only, get_startup_report,
+    get_store_snapshot, import_store_snapshot, list_tasks, reject_when_read_only, repair_approvals,
+    set_read_only, store_stats, verify_audit,
+};
+use crate::clock::Clock;
+use crate::deadline::enforce_request_deadline;
+use crate::delay::{inject_request_delays, RequestDelays};
 use crate::handlers::{
-    cancel_approval, create_operation_request, deny_approval, get_contracts, get_run,
-    get_run_audit, grant_approval, healthz, submit_step_intent, submit_step_result,
+    cancel_approval, check_policy, create_operation_request, deny_approval, get_approval,
+    get_audit_checkpoints, get_audit_proof, get_contracts, get_run, get_run_audit, grant_approval,
+    healthz, readyz, submit_step_intent, submit_step_result,
+};
+use crate::integrity::{fsck_store, run_startup_check};
+use crate::listener::resolve_listener;
+use crate::lookup::lookup;
+use crate::maintenance::{
+    spawn_archive_sweeper, spawn_checkpoint_publisher, spawn_idempotency_gc, spawn_vacuum,
+    ARCHIVE_TASK, CHECKPOINT_PUBLISH_TASK, IDEMPOTENCY_GC_TASK, VACUUM_TASK,
 };
+use crate::recovery::recover_panics;
+use crate::s3::{spawn_audit_s3_uploader, AuditShipper, AUDIT_S3_TASK};
+use crate::startup::build_startup_report;
 use crate::store::AppState;
 
 pub use audit::{verify_audit_chain, verify_audit_chain_with_mirror};
+pub use backup::{backup_to_dir, restore_from_dir};
+pub use policy_diff::policy_diff;
+pub use policy_test::{policy_test, PolicyTestReport};
 
 pub async fn serve(cfg: Config) -> Result<(), String> {
-    let addr: SocketAddr = cfg
-        .server
-        .listen_addr
-        .parse()
-        .map_err(|err| format!("invalid listen_addr: {err}"))?;
-    let app = build_app(cfg).await?;
-    let listener = tokio::net::TcpListener::bind(addr)
-        .await
-        .map_err(|err| format!("bind failed: {err}"))?;
+    serve_with_listener_fd(cfg, None).await
+}
+
+/// Serves on an already-bound listener when `listener_fd` is set, so a new
+/// binary can take over a socket without a bind/unbind gap. In-flight
+/// requests are drained on SIGTERM or Ctrl-C before returning, then the
+/// memory store is written to `store.memory.snapshot_path` if set and, with
+/// `audit.sink=s3`, the records not yet uploaded are shipped.
+pub async fn serve_with_listener_fd(cfg: Config, listener_fd: Option<i32>) -> Result<(), String> {
+    let listener = resolve_listener(&cfg.server.listen_addr, cfg.server.reuse_port, listener_fd)?;
+    let snapshot_path = cfg.store.memory.snapshot_path.clone();
+    let audit = cfg.audit.clone();
+    let _store_lock = wait_for_sqlite_store(&cfg).await?;
+    let state = AppState::new(cfg.clone())?;
+    let (app, report) = build_app_with_report(cfg, state.clone()).await?;
+    // stderr, because stdout may carry the audit stream.
+    match serde_json::to_string(&report) {
+        Ok(line) => eprintln!("{line}"),
+        Err(err) => eprintln!("failed to encode startup report: {err}"),
+    }
     axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .map_err(|err| format!("serve failed: {err}"))?;
+    if let Some(path) = snapshot_path {
+        save_memory_snapshot(&state, &path).await?;
+    }
+    if audit.s3.is_some() {
+        AuditShipper::new(&audit)?
+            .ship_pending(&state)
+            .await
+            .map_err(|err| format!("final audit s3 upload failed: {err}"))?;
+    }
+    Ok(())
+}
+
+/// Loads `store.memory.snapshot_path` into the still-empty memory store. A
+/// missing file is a first start, not an error.
+async fn load_memory_snapshot(state: &AppState, path: &str) -> Result<(), String> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(format!("failed to read memory snapshot {path}: {err}")),
+    };
+    let snapshot: StoreSnapshot = serde_json::from_str(&text)
+        .map_err(|err| format!("invalid memory snapshot {path}: {err}"))?;
+    state
+        .lock_store()
+        .await
+        .import_snapshot(snapshot)
+        .map_err(|err| format!("failed to load memory snapshot {path}: {err:?}"))
+}
+
+/// Writes the memory store to `path` through a temporary file and a rename,
+/// so an interrupted write leaves the previous snapshot in place.
+async fn save_memory_snapshot(state: &AppState, path: &str) -> Result<(), String> {
+    let snapshot = state
+        .lock_store()
         .await
-        .map_err(|err| format!("serve failed: {err}"))
+        .export_snapshot()
+        .map_err(|err| format!("failed to export memory snapshot: {err:?}"))?;
+    let text = serde_json::to_string_pretty(&snapshot)
+        .map_err(|err| format!("failed to encode memory snapshot: {err}"))?;
+    let tmp_path = format!("{path}.tmp");
+    std::fs::write(&tmp_path, text + "\n")
+        .map_err(|err| format!("failed to write {tmp_path}: {err}"))?;
+    std::fs::rename(&tmp_path, path)
+        .map_err(|err| format!("failed to replace memory snapshot {path}: {err}"))
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
 
 pub async fn build_app(cfg: Config) -> Result<Router, String> {
-    let state = AppState::new(cfg)?;
-    Ok(Router::new()
-        .route("/v1/healthz", get(healthz))
-        .route("/v1/contracts", get(get_contracts))
+    let state = AppState::new(cfg.clone())?;
+    build_app_with_report(cfg, state).await.map(|(app, _)| app)
+}
+
+/// Builds the app on a caller-supplied store instead of the backend named by
+/// `store.kind`. The backend-specific `store` settings (`sqlite_path`,
+/// `sqlite`, `idempotency_journal_path`) are then unused.
+pub async fn build_app_with_store(cfg: Config, store: Box<dyn Store>) -> Result<Router, String> {
+    let clock = Clock::from_config(cfg.determinism.as_ref())?;
+    let state = AppState::with_backend(cfg.clone(), store, clock)?;
+    build_app_with_report(cfg, state).await.map(|(app, _)| app)
+}
+
+async fn build_app_with_report(
+    cfg: Config,
+    state: AppState,
+) -> Result<(Router, StartupReport), String> {
+    let report_cfg = cfg.clone();
+    let startup_check = cfg.server.startup_check.clone();
+    let archive_after = cfg.store.archive_after;
+    let archive_interval = cfg.store.archive_interval;
+    let idempotency_gc_interval = cfg.store.idempotency_gc_interval;
+    let gc_batch_size = cfg.store.gc_batch_size as usize;
+    let vacuum_interval = cfg.store.vacuum_interval;
+    let vacuum_window = cfg.store.vacuum_window;
+    let checkpoint_publish_url = cfg.audit.checkpoint_publish_url.clone();
+    let checkpoint_publish_interval = cfg.audit.checkpoint_publish_interval;
+    let audit_config = cfg.audit.clone();
+    let request_timeout = cfg.server.request_timeout;
+    let request_delays = cfg
+        .determinism
+        .as_ref()
+        .is_some_and(|determinism| determinism.request_delays);
+    if let Some(path) = &cfg.store.memory.snapshot_path {
+        load_memory_snapshot(&state, path).await?;
+    }
+    if startup_check.enabled {
+        run_startup_check(&state, &startup_check).await?;
+    }
+    state
+        .lock_store()
+        .await
+        .reanchor_audit_if_needed()
+        .map_err(|err| format!("failed to re-anchor audit chain: {err:?}"))?;
+    let mut background_tasks = vec![IDEMPOTENCY_GC_TASK.to_string()];
+    if archive_after.is_some() {
+        background_tasks.push(ARCHIVE_TASK.to_string());
+    }
+    if vacuum_interval.is_some() {
+        background_tasks.push(VACUUM_TASK.to_string());
+    }
+    if checkpoint_publish_url.is_some() {
+        background_tasks.push(CHECKPOINT_PUBLISH_TASK.to_string());
+    }
+    if audit_config.s3.is_some() {
+        background_tasks.push(AUDIT_S3_TASK.to_string());
+    }
+    let report = build_startup_report(&report_cfg, &state, background_tasks).await?;
+    let state = state.with_startup_report(report.clone());
+    spawn_idempotency_gc(state.clone(), idempotency_gc_interval, gc_batch_size);
+    if let Some(archive_after) = archive_after {
+        spawn_archive_sweeper(
+            state.clone(),
+            archive_after,
+            archive_interval,
+            gc_batch_size,
+        );
+    }
+    if let Some(interval) = vacuum_interval {
+        spawn_vacuum(state.clone(), interval, vacuum_window, gc_batch_size);
+    }
+    if let Some(url) = checkpoint_publish_url {
+        spawn_checkpoint_publisher(state.clone(), url, checkpoint_publish_interval)?;
+    }
+    if audit_config.s3.is_some() {
+        spawn_audit_s3_uploader(state.clone(), &audit_config)?;
+    }
+    let mutating = Router::new()
         .route("/v1/operation-requests", post(create_operation_request))
-        .route("/v1/runs/{run_id}", get(get_run))
         .route("/v1/runs/{run_id}/step-intents", post(submit_step_intent))
         .route("/v1/runs/{run_id}/step-results", post(submit_step_result))
-        .route("/v1/audit/runs/{run_id}", get(get_run_audit))
         .route("/v1/approvals/{approval_id}/grant", post(grant_approval))
         .route("/v1/approvals/{approval_id}/deny", post(deny_approval))
         .route("/v1/approvals/{approval_id}/cancel", post(cancel_approval))
-        .with_state(state))
+        .route("/v1/admin/repair/approvals", post(repair_approvals))
+        .route_layer(from_fn_with_state(state.clone(), reject_when_read_only));
+    // Store transfers scale with the store and commit even if the client
+    // gives up, so they are left out of the request deadline.
+    let mut transfers = Router::new()
+        .route("/v1/admin/store/backup", post(backup_store))
+        .route("/v1/admin/store/export", get(get_store_snapshot))
+        .route(
+            "/v1/admin/store/import",
+            post(import_store_snapshot)
+                .route_layer(from_fn_with_state(state.clone(), reject_when_read_only)),
+        );
+    let mut app = Router::new()
+        .route("/v1/healthz", get(healthz))
+        .route("/v1/readyz", get(readyz))
+        .route("/v1/contracts", get(get_contracts))
+        .route("/v1/runs/{run_id}", get(get_run))
+        .route("/v1/audit/runs/{run_id}", get(get_run_audit))
+        .route("/v1/audit/checkpoints", get(get_audit_checkpoints))
+        .route("/v1/audit/{audit_id}/proof", get(get_audit_proof))
+        .route("/v1/lookup/{id}", get(lookup))
+        .route("/v1/policy/check", post(check_policy))
+        .route("/v1/approvals/{approval_id}", get(get_approval))
+        .route("/v1/admin/store/stats", get(store_stats))
+        .route("/v1/admin/audit/verify", get(verify_audit))
+        .route("/v1/admin/startup", get(get_startup_report))
+        .route("/v1/admin/read-only", get(get_read_only).put(set_read_only))
+        .route("/v1/admin/tasks", get(list_tasks))
+        .route("/v1/admin/panics", get(get_panics))
+        .route("/v1/admin/idempotency/{key}", get(get_idempotency_entry))
+        .merge(mutating);
+    if request_delays {
+        let delays = RequestDelays::default();
+        app = app.layer(from_fn_with_state(delays.clone(), inject_request_delays));
+        transfers = transfers.layer(from_fn_with_state(delays, inject_request_delays));
+    }
+    let app = app
+        .layer(from_fn_with_state(
+            request_timeout,
+            enforce_request_deadline,
+        ))
+        .merge(transfers)
+        .layer(from_fn_with_state(state.clone(), recover_panics))
+        .with_state(state);
+    Ok((app, report))
 }
 
 pub async fn doctor(cfg: Config) -> Result<Vec<String>, String> {
-    let state = AppState::new(cfg)?;
+    let (state, _store_lock) = open_for_reading(cfg)?;
     let store = state.lock_store().await;
     store
         .doctor()
         .map_err(|err| format!("doctor failed: {err:?}"))
 }
+
+pub async fn backup(cfg: Config, out_path: &str) -> Result<String, String> {
+    let (state, _store_lock) = open_for_reading(cfg)?;
+    let store = state.lock_store().await;
+    store
+        .backup(out_path)
+        .map_err(|err| format!("backup failed: {err:?}"))?;
+    Ok(format!("store backup written: {out_path}"))
+}
+
+/// Runs the store consistency checks behind `arbiter store-fsck`. Returns
+/// the report lines and the number of findings left unrepaired.
+pub async fn fsck(cfg: Config, repair: bool) -> Result<(Vec<String>, usize), String> {
+    reject_offline_memory_store(&cfg, "POST /v1/admin/repair/approvals")?;
+    let _store_lock = lock_sqlite_store(&cfg)?;
+    let state = AppState::new(cfg)?;
+    let mut store = state.lock_store().await;
+    let report = fsck_store(&mut store, repair).map_err(|err| format!("fsck failed: {err:?}"))?;
+    let mut lines = vec![
+        format!("runs={}", report.integrity.scanned_runs),
+        format!("archived_runs={}", report.archived_runs),
+        format!("idempotency_records={}", report.idempotency_records),
+    ];
+    let mut unresolved = 0;
+    for finding in &report.integrity.findings {
+        let status = if finding.repaired {
+            "repaired"
+        } else {
+            unresolved += 1;
+            "found"
+        };
+        lines.push(match &finding.run_id {
+            Some(run_id) => format!("{status}: {run_id}: {}", finding.issue),
+            None => format!("{status}: {}", finding.issue),
+        });
+    }
+    lines.push(format!(
+        "findings={} unresolved={unresolved}",
+        report.integrity.findings.len()
+    ));
+    Ok((lines, unresolved))
+}
+
+/// Writes a `StoreSnapshot` of the configured store to `out_path`. A memory
+/// store only has state inside the server process, so it is exported over
+/// `GET /v1/admin/store/export` instead.
+pub async fn export_store(cfg: Config, out_path: &str) -> Result<String, String> {
+    reject_offline_memory_store(&cfg, "GET /v1/admin/store/export")?;
+    let (state, _store_lock) = open_for_reading(cfg)?;
+    let snapshot = state
+        .lock_store()
+        .await
+        .export_snapshot()
+        .map_err(|err| format!("export failed: {err:?}"))?;
+    let text = serde_json::to_string_pretty(&snapshot)
+        .map_err(|err| format!("failed to encode snapshot: {err}"))?;
+    std::fs::write(out_path, text + "\n")
+        .map_err(|err| format!("failed to write {out_path}: {err}"))?;
+    Ok(format!(
+        "store snapshot written: {out_path} (runs={}, archived_runs={}, approval_mappings={}, idempotency_records={})",
+        snapshot.runs.len(),
+        snapshot.archived_runs.len(),
+        snapshot.approval_mappings.len(),
+        snapshot.idempotency_records.len()
+    ))
+}
+
+/// Loads a snapshot written by `export_store` into the configured store,
+/// which must be empty.
+pub async fn import_store(cfg: Config, in_path: &str) -> Result<String, String> {
+    reject_offline_memory_store(&cfg, "POST /v1/admin/store/import")?;
+    let text = std::fs::read_to_string(in_path)
+        .map_err(|err| format!("failed to read {in_path}: {err}"))?;
+    let snapshot: StoreSnapshot =
+        serde_json::from_str(&text).map_err(|err| format!("invalid snapshot {in_path}: {err}"))?;
+    let _store_lock = lock_sqlite_store(&cfg)?;
+    let state = AppState::new(cfg)?;
+    let mut store = state.lock_store().await;
+    store
+        .import_snapshot(snapshot)
+        .map_err(|err| format!("import failed: {err:?}"))?;
+    let stats = store
+        .stats()
+        .map_err(|err| format!("import failed: {err:?}"))?;
+    Ok(format!(
+        "store snapshot imported: {in_path} (runs={}, archived_runs={}, approval_mappings={}, idempotency_records={})",
+        stats.runs, stats.archived_runs, stats.approval_mappings, stats.idempotency_records
+    ))
+}
+
+/// Takes the exclusive lock on `<store.sqlite_path>.lock`, held by
+/// `arbiter serve` for as long as it runs. Offline commands that migrate the
+/// store or append audit records take it too: a second writer would fork
+/// the hash chain, whose head the server keeps in memory.
+fn lock_sqlite_store(cfg: &Config) -> Result<Option<std::fs::File>, String> {
+    match try_lock_sqlite_store(cfg)? {
+        StoreLock::Held(file) => Ok(file),
+        StoreLock::Busy(sqlite_path) => Err(format!(
+            "store {sqlite_path} is in use by another arbiter process; stop the server first"
+        )),
+    }
+}
+
+/// Opens the store for commands that only read it and may run next to
+/// `arbiter serve`. A free store lock is held for the duration, so pending
+/// migrations are applied as usual; a store the server holds is opened
+/// without migrating, since only the lock holder may change its schema.
+fn open_for_reading(cfg: Config) -> Result<(AppState, Option<std::fs::File>), String> {
+    match try_lock_sqlite_store(&cfg)? {
+        StoreLock::Held(file) => Ok((AppState::new(cfg)?, file)),
+        StoreLock::Busy(_) => Ok((AppState::without_migrating(cfg)?, None)),
+    }
+}
+
+/// `arbiter serve` waits for the store lock instead of failing, so a new
+/// instance bound next to a draining one (`server.reuse_port`, `--fd`)
+/// opens the store only once the old one has exited. Connections it accepts
+/// meanwhile queue on the listener.
+async fn wait_for_sqlite_store(cfg: &Config) -> Result<Option<std::fs::File>, String> {
+    let mut announced = false;
+    loop {
+        match try_lock_sqlite_store(cfg)? {
+            StoreLock::Held(file) => return Ok(file),
+            StoreLock::Busy(sqlite_path) => {
+                if !announced {
+                    eprintln!(
+                        "store {sqlite_path} is in use by another arbiter process; waiting for it to exit"
+                    );
+                    announced = true;
+                }
+                tokio::time::sleep(STORE_LOCK_POLL).await;
+            }
+        }
+    }
+}
+
+/// How often `arbiter serve` retries a store lock held by another process.
+const STORE_LOCK_POLL: std::time::Duration = std::time::Duration::from_millis(100);
+
+enum StoreLock {
+    /// The lock file, or `None` when the store is not sqlite.
+    Held(Option<std::fs::File>),
+    /// Another process holds the lock on this sqlite path.
+    Busy(String),
+}
+
+fn try_lock_sqlite_store(cfg: &Config) -> Result<StoreLock, String> {
+    let Some(sqlite_path) = cfg.store.sqlite_path.as_deref() else {
+        return Ok(StoreLock::Held(None));
+    };
+    if cfg.store.kind != "sqlite" {
+        return Ok(StoreLock::Held(None));
+    }
+    let lock_path = format!("{sqlite_path}.lock");
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|err| format!("failed to open {lock_path}: {err}"))?;
+    match file.try_lock() {
+        Ok(()) => Ok(StoreLock::Held(Some(file))),
+        Err(std::fs::TryLockError::WouldBlock) => Ok(StoreLock::Busy(sqlite_path.to_string())),
+        Err(std::fs::TryLockError::Error(err)) => Err(format!("failed to lock {lock_path}: {err}")),
+    }
+}
+
+fn reject_offline_memory_store(cfg: &Config, endpoint: &str) -> Result<(), String> {
+    if cfg.store.kind == "memory" {
+        return Err(format!(
+            "store.kind=memory keeps its state inside the running server; use {endpoint}"
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use std::net::SocketAddr;
+
+/// First file descriptor passed by systemd socket activation.
+#[cfg(unix)]
+const SD_LISTEN_FDS_START: i32 = 3;
+
+/// Resolves the listening socket. An explicit `inherited_fd` wins, then a
+/// systemd-activated socket (`LISTEN_PID`/`LISTEN_FDS`), and only then a
+/// fresh bind of `listen_addr`, optionally with `SO_REUSEPORT`.
+pub(crate) fn resolve_listener(
+    listen_addr: &str,
+    reuse_port: bool,
+    inherited_fd: Option<i32>,
+) -> Result<tokio::net::TcpListener, String> {
+    if let Some(fd) = inherited_fd.or_else(systemd_listen_fd) {
+        return listener_from_fd(fd);
+    }
+
+    let addr: SocketAddr = listen_addr
+        .parse()
+        .map_err(|err| format!("invalid listen_addr: {err}"))?;
+    let socket = socket2::Socket::new(
+        socket2::Domain::for_address(addr),
+        socket2::Type::STREAM,
+        Some(socket2::Protocol::TCP),
+    )
+    .map_err(|err| format!("bind failed: {err}"))?;
+    socket
+        .set_reuse_address(true)
+        .map_err(|err| format!("bind failed: {err}"))?;
+    if reuse_port {
+        set_reuse_port(&socket)?;
+    }
+    socket
+        .set_nonblocking(true)
+        .map_err(|err| format!("bind failed: {err}"))?;
+    socket
+        .bind(&addr.into())
+        .map_err(|err| format!("bind failed: {err}"))?;
+    socket
+        .listen(1024)
+        .map_err(|err| format!("bind failed: {err}"))?;
+    tokio::net::TcpListener::from_std(socket.into()).map_err(|err| format!("bind failed: {err}"))
+}
+
+#[cfg(unix)]
+fn set_reuse_port(socket: &socket2::Socket) -> Result<(), String> {
+    socket
+        .set_reuse_port(true)
+        .map_err(|err| format!("failed to set SO_REUSEPORT: {err}"))
+}
+
+#[cfg(not(unix))]
+fn set_reuse_port(_socket: &socket2::Socket) -> Result<(), String> {
+    Err("server.reuse_port is only supported on unix".to_string())
+}
+
+#[cfg(unix)]
+fn systemd_listen_fd() -> Option<i32> {
+    let pid: u32 = std::env::var("LISTEN_PID").ok()?.parse().ok()?;
+    let fds: i32 = std::env::var("LISTEN_FDS").ok()?.parse().ok()?;
+    (pid == std::process::id() && fds >= 1).then_some(SD_LISTEN_FDS_START)
+}
+
+#[cfg(not(unix))]
+fn systemd_listen_fd() -> Option<i32> {
+    None
+}
+
+#[cfg(unix)]
+fn listener_from_fd(fd: i32) -> Result<tokio::net::TcpListener, String> {
+    use std::os::unix::io::FromRawFd;
+
+    if fd < 0 {
+        return Err(format!("invalid inherited listener fd: {fd}"));
+    }
+    // SAFETY: the fd was handed to this process by its supervisor for the
+    // sole purpose of serving; nothing else in the process owns it.
+    let listener = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+    listener
+        .set_nonblocking(true)
+        .map_err(|err| format!("inherited listener fd {fd} is unusable: {err}"))?;
+    tokio::net::TcpListener::from_std(listener)
+        .map_err(|err| format!("inherited listener fd {fd} is unusable: {err}"))
+}
+
+#[cfg(not(unix))]
+fn listener_from_fd(fd: i32) -> Result<tokio::net::TcpListener, String> {
+    Err(format!(
+        "inheriting listener fd {fd} is only supported on unix"
+    ))
+}
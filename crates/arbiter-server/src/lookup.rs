@@ -0,0 +1,82 @@
+use arbiter_contracts::{LookupKind, LookupMatch, LookupResponse};
+use axum::extract::{Path as AxPath, State};
+use axum::Json;
+use std::collections::BTreeMap;
+
+use crate::audit::find_audit_event;
+use crate::errors::{into_error, ApiErrorResponse, ApiFailure};
+use crate::store::{AppState, StoreBackend};
+
+/// `event_id` format of audit records (`ops.audit_record`).
+const AUDIT_EVENT_ID_PREFIX: &str = "evt_";
+
+/// Resolves any identifier Arbiter issues or accepts to the records it
+/// names. Step ids are only unique within a run, so several matches are
+/// possible.
+pub(crate) async fn lookup(
+    State(state): State<AppState>,
+    AxPath(id): AxPath<String>,
+) -> Result<Json<LookupResponse>, ApiErrorResponse> {
+    let needle = id.clone();
+    let matches = state
+        .with_store(move |store| find_matches(store, &needle))
+        .await
+        .and_then(|matches| matches)
+        .map_err(into_error)?;
+    if matches.is_empty() {
+        return Err(into_error(ApiFailure::not_found(
+            "not_found",
+            "no record matches this id",
+        )));
+    }
+    Ok(Json(LookupResponse { id, matches }))
+}
+
+fn find_matches(store: &StoreBackend, id: &str) -> Result<Vec<LookupMatch>, ApiFailure> {
+    let mut matches: Vec<LookupMatch> = store
+        .lookup_id(id)?
+        .into_iter()
+        .map(|m| {
+            let approval = m.kind == LookupKind::Approval;
+            let mut found = found(m.kind, &m.run_id, m.archived);
+            if approval {
+                found
+                    .links
+                    .insert("approval".to_string(), format!("/v1/approvals/{id}"));
+            }
+            found
+        })
+        .collect();
+    // Audit records are not indexed: an audit event id is looked for by
+    // reading the kept audit files, so only ids in that format pay for it.
+    let audit_event = match store.audit_path() {
+        Some(path) if id.starts_with(AUDIT_EVENT_ID_PREFIX) => find_audit_event(path, id)?,
+        _ => None,
+    };
+    if let Some((event, live)) = audit_event {
+        let mut m = found(LookupKind::AuditEvent, &event.run_id, false);
+        if live {
+            m.links
+                .insert("proof".to_string(), format!("/v1/audit/{id}/proof"));
+        }
+        matches.push(m);
+    }
+    Ok(matches)
+}
+
+fn found(kind: LookupKind, run_id: &str, archived: bool) -> LookupMatch {
+    let run_link = if archived {
+        format!("/v1/runs/{run_id}?include_archived=true")
+    } else {
+        format!("/v1/runs/{run_id}")
+    };
+    LookupMatch {
+        kind,
+        run_id: run_id.to_string(),
+        archived,
+        links: BTreeMap::from([
+            ("run".to_string(), run_link),
+            ("audit".to_string(), format!("/v1/audit/runs/{run_id}")),
+        ]),
+    }
+}
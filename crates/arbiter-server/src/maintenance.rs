@@ -0,0 +1,173 @@
+use arbiter_config::TimeWindow;
+use std::time::Duration as StdDuration;
+use tokio::time::Instant;
+
+use crate::audit::list_checkpoints;
+use crate::store::{to_chrono, AppState};
+use crate::tasks::{run_batched_pass, run_pass};
+
+pub(crate) const ARCHIVE_TASK: &str = "run_archive_sweeper";
+pub(crate) const IDEMPOTENCY_GC_TASK: &str = "idempotency_gc";
+pub(crate) const VACUUM_TASK: &str = "sqlite_vacuum";
+pub(crate) const CHECKPOINT_PUBLISH_TASK: &str = "audit_checkpoint_publisher";
+
+/// Longest wait between checks for a due vacuum pass, so a pass deferred
+/// by the window starts soon after the window opens.
+const VACUUM_POLL_INTERVAL: StdDuration = StdDuration::from_secs(60);
+
+/// Periodically moves terminal runs older than `archive_after` into
+/// the archive, `batch_size` runs per store lock. The first sweep runs
+/// immediately at startup; sweeps are skipped while read-only mode is on.
+pub(crate) fn spawn_archive_sweeper(
+    state: AppState,
+    archive_after: StdDuration,
+    interval: StdDuration,
+    batch_size: usize,
+) {
+    state.tasks().register(ARCHIVE_TASK);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if state.is_read_only() {
+                continue;
+            }
+            let threshold = state.clock().now() - to_chrono(archive_after);
+            run_batched_pass(&state, ARCHIVE_TASK, batch_size, move |store, limit| {
+                store.archive_terminal_runs(threshold, limit)
+            })
+            .await;
+        }
+    });
+}
+
+/// Periodically deletes idempotency records older than
+/// `governance.idempotency_retention`, so the table stays bounded even
+/// for keys that are never replayed. Skipped while read-only mode is on.
+pub(crate) fn spawn_idempotency_gc(state: AppState, interval: StdDuration, batch_size: usize) {
+    state.tasks().register(IDEMPOTENCY_GC_TASK);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if state.is_read_only() {
+                continue;
+            }
+            run_batched_pass(&state, IDEMPOTENCY_GC_TASK, batch_size, |store, limit| {
+                store.prune_expired_idempotency(limit)
+            })
+            .await;
+        }
+    });
+}
+
+/// Runs incremental VACUUM and then ANALYZE on the sqlite store once every
+/// `interval`, releasing `batch_size` free pages per store lock. The first
+/// pass runs at startup; with a `window`, a due pass waits until the clock
+/// is inside it. Skipped while read-only mode is on.
+pub(crate) fn spawn_vacuum(
+    state: AppState,
+    interval: StdDuration,
+    window: Option<TimeWindow>,
+    batch_size: usize,
+) {
+    state.tasks().register(VACUUM_TASK);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval.min(VACUUM_POLL_INTERVAL));
+        let mut last_pass: Option<Instant> = None;
+        loop {
+            ticker.tick().await;
+            if state.is_read_only() || last_pass.is_some_and(|at| at.elapsed() < interval) {
+                continue;
+            }
+            if window.is_some_and(|window| !window.contains(state.clock().now().time())) {
+                continue;
+            }
+            last_pass = Some(Instant::now());
+            run_batched_pass(&state, VACUUM_TASK, batch_size, |store, limit| {
+                store.vacuum(limit)
+            })
+            .await;
+        }
+    });
+}
+
+/// Posts each completed audit checkpoint to an external append-only log.
+/// Progress is kept in `<audit.jsonl_path>.published` so restarts resume
+/// after the last acknowledged segment; failures are retried next tick.
+pub(crate) fn spawn_checkpoint_publisher(
+    state: AppState,
+    url: String,
+    interval: StdDuration,
+) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(StdDuration::from_secs(10))
+        .build()
+        .map_err(|err| format!("failed to build checkpoint publisher client: {err}"))?;
+    state.tasks().register(CHECKPOINT_PUBLISH_TASK);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let pass = publish_pending_checkpoints(&state, &client, &url);
+            if let Err(err) = run_pass(&state, CHECKPOINT_PUBLISH_TASK, pass).await {
+                eprintln!("audit checkpoint publish failed: {err}");
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Returns the number of segments published.
+async fn publish_pending_checkpoints(
+    state: &AppState,
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<u64, String> {
+    let interval = state.audit_checkpoint_interval();
+    let (audit_path, checkpoints) = state
+        .with_store(move |store| {
+            let path = store.audit_log()?;
+            list_checkpoints(path, interval).map(|listed| (path.to_string(), listed.checkpoints))
+        })
+        .await
+        .and_then(|listed| listed)
+        .map_err(|err| format!("{err:?}"))?;
+    let cursor_path = format!("{audit_path}.published");
+    let published = read_publish_cursor(&cursor_path)?;
+
+    let mut count = 0;
+    for checkpoint in checkpoints
+        .iter()
+        .filter(|c| c.complete && c.segment_index >= published)
+    {
+        let res = client
+            .post(url)
+            .json(checkpoint)
+            .send()
+            .await
+            .map_err(|err| format!("segment {}: {err}", checkpoint.segment_index))?;
+        if !res.status().is_success() {
+            return Err(format!(
+                "segment {}: publisher returned {}",
+                checkpoint.segment_index,
+                res.status()
+            ));
+        }
+        std::fs::write(&cursor_path, (checkpoint.segment_index + 1).to_string())
+            .map_err(|err| format!("failed to write {cursor_path}: {err}"))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn read_publish_cursor(path: &str) -> Result<u64, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => text
+            .trim()
+            .parse()
+            .map_err(|err| format!("invalid publish cursor in {path}: {err}")),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(err) => Err(format!("failed to read {path}: {err}")),
+    }
+}
@@ -0,0 +1,106 @@
+use arbiter_config::Config;
+use arbiter_contracts::{DecisionEffect, PolicyCheckRequest};
+use arbiter_kernel::policy::PolicyDecision;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::handlers::evaluate_check;
+use crate::store::{approver_config_from, policy_config_from};
+
+#[derive(Debug, Serialize)]
+struct DecisionSummary {
+    effect: DecisionEffect,
+    reason: String,
+    applied_policies: Vec<String>,
+    required_approvers: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ChangedDecision {
+    line: usize,
+    environment: String,
+    provider: String,
+    capability: String,
+    intent_type: String,
+    risk_level: String,
+    a: DecisionSummary,
+    b: DecisionSummary,
+}
+
+#[derive(Debug, Serialize)]
+struct PolicyDiffReport {
+    events: usize,
+    changed: usize,
+    by_reason: BTreeMap<String, usize>,
+    changes: Vec<ChangedDecision>,
+}
+
+impl DecisionSummary {
+    fn from_decision(decision: PolicyDecision) -> Self {
+        Self {
+            effect: decision.effect,
+            reason: decision
+                .applied_policies
+                .last()
+                .cloned()
+                .unwrap_or_default(),
+            applied_policies: decision.applied_policies,
+            required_approvers: decision.required_approvers,
+        }
+    }
+
+    fn same_as(&self, other: &Self) -> bool {
+        self.effect == other.effect
+            && self.applied_policies == other.applied_policies
+            && self.required_approvers == other.required_approvers
+    }
+}
+
+/// Replays captured policy checks (one `PolicyCheckRequest` per JSONL line)
+/// against two configs in-process and reports every decision that differs.
+/// `by_reason` counts changes keyed `<reason a> -> <reason b>`, where the
+/// reason is the rule that decided the outcome.
+pub fn policy_diff(cfg_a: &Config, cfg_b: &Config, events_path: &str) -> Result<String, String> {
+    let text = std::fs::read_to_string(events_path)
+        .map_err(|err| format!("failed to read {events_path}: {err}"))?;
+    let (policy_a, approvers_a) = (policy_config_from(cfg_a), approver_config_from(cfg_a));
+    let (policy_b, approvers_b) = (policy_config_from(cfg_b), approver_config_from(cfg_b));
+
+    let mut report = PolicyDiffReport {
+        events: 0,
+        changed: 0,
+        by_reason: BTreeMap::new(),
+        changes: Vec::new(),
+    };
+    for (idx, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let input: PolicyCheckRequest = serde_json::from_str(line)
+            .map_err(|err| format!("invalid policy check at line {}: {err}", idx + 1))?;
+        report.events += 1;
+        let a = DecisionSummary::from_decision(evaluate_check(&input, &policy_a, &approvers_a));
+        let b = DecisionSummary::from_decision(evaluate_check(&input, &policy_b, &approvers_b));
+        if a.same_as(&b) {
+            continue;
+        }
+        report.changed += 1;
+        *report
+            .by_reason
+            .entry(format!("{} -> {}", a.reason, b.reason))
+            .or_default() += 1;
+        report.changes.push(ChangedDecision {
+            line: idx + 1,
+            environment: input.environment,
+            provider: input.intent.provider,
+            capability: input.intent.capability,
+            intent_type: input.intent.intent_type,
+            risk_level: input.intent.risk_level,
+            a,
+            b,
+        });
+    }
+
+    serde_json::to_string_pretty(&report)
+        .map_err(|err| format!("failed to encode policy diff report: {err}"))
+}
@@ -0,0 +1,167 @@
+use arbiter_contracts::{DecisionEffect, PolicyCheckRequest};
+use serde::Deserialize;
+use std::fmt;
+
+use crate::handlers::evaluate_check;
+use crate::store::{approver_config_from, policy_config_from};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyTestFile {
+    cases: Vec<PolicyTestCase>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyTestCase {
+    name: String,
+    #[serde(default)]
+    config: Option<serde_json::Value>,
+    check: PolicyCheckRequest,
+    expect: Expectation,
+}
+
+/// Unset fields are not compared, so a case only pins what it is about.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Expectation {
+    effect: DecisionEffect,
+    #[serde(default)]
+    reason: Option<String>,
+    #[serde(default)]
+    applied_policies: Option<Vec<String>>,
+    #[serde(default)]
+    required_approvers: Option<Vec<String>>,
+}
+
+#[derive(Debug)]
+struct CaseResult {
+    file: String,
+    name: String,
+    mismatches: Vec<String>,
+}
+
+/// Outcome of `policy_test`; `Display` prints one line per case and a
+/// closing tally.
+#[derive(Debug)]
+pub struct PolicyTestReport {
+    results: Vec<CaseResult>,
+}
+
+impl PolicyTestReport {
+    pub fn cases(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|result| !result.mismatches.is_empty())
+            .count()
+    }
+}
+
+impl fmt::Display for PolicyTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            if result.mismatches.is_empty() {
+                writeln!(f, "ok   {}: {}", result.file, result.name)?;
+            } else {
+                writeln!(
+                    f,
+                    "FAIL {}: {}: {}",
+                    result.file,
+                    result.name,
+                    result.mismatches.join("; ")
+                )?;
+            }
+        }
+        write!(
+            f,
+            "{} passed, {} failed",
+            self.cases() - self.failed(),
+            self.failed()
+        )
+    }
+}
+
+/// Runs YAML policy test cases against the kernel in-process. Each case
+/// names a `PolicyCheckRequest`, the decision it expects, and optionally a
+/// config fragment deep-merged into `config_text` for that case only. No
+/// store or audit log is opened.
+pub fn policy_test(config_text: &str, case_paths: &[String]) -> Result<PolicyTestReport, String> {
+    let base = arbiter_config::parse_and_validate(config_text)
+        .map_err(|err| format!("invalid base config: {err}"))?;
+
+    let mut results = Vec::new();
+    for path in case_paths {
+        let text =
+            std::fs::read_to_string(path).map_err(|err| format!("failed to read {path}: {err}"))?;
+        let file: PolicyTestFile =
+            serde_yaml::from_str(&text).map_err(|err| format!("invalid {path}: {err}"))?;
+        for case in file.cases {
+            let cfg = match &case.config {
+                Some(fragment) => {
+                    arbiter_config::parse_and_validate_with_overlay(config_text, fragment).map_err(
+                        |err| format!("{path}: case {:?} has an invalid config: {err}", case.name),
+                    )?
+                }
+                None => base.clone(),
+            };
+            let decision = evaluate_check(
+                &case.check,
+                &policy_config_from(&cfg),
+                &approver_config_from(&cfg),
+            );
+
+            let expect = case.expect;
+            let mut mismatches = Vec::new();
+            if decision.effect != expect.effect {
+                mismatches.push(format!(
+                    "effect: expected {}, got {}",
+                    effect_name(&expect.effect),
+                    effect_name(&decision.effect)
+                ));
+            }
+            let reason = decision
+                .applied_policies
+                .last()
+                .cloned()
+                .unwrap_or_default();
+            if let Some(expected) = expect.reason.filter(|expected| *expected != reason) {
+                mismatches.push(format!("reason: expected {expected}, got {reason}"));
+            }
+            if let Some(expected) = expect
+                .applied_policies
+                .filter(|expected| *expected != decision.applied_policies)
+            {
+                mismatches.push(format!(
+                    "applied_policies: expected {expected:?}, got {:?}",
+                    decision.applied_policies
+                ));
+            }
+            if let Some(expected) = expect
+                .required_approvers
+                .filter(|expected| *expected != decision.required_approvers)
+            {
+                mismatches.push(format!(
+                    "required_approvers: expected {expected:?}, got {:?}",
+                    decision.required_approvers
+                ));
+            }
+            results.push(CaseResult {
+                file: path.clone(),
+                name: case.name,
+                mismatches,
+            });
+        }
+    }
+    Ok(PolicyTestReport { results })
+}
+
+fn effect_name(effect: &DecisionEffect) -> String {
+    serde_json::to_value(effect)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_else(|| format!("{effect:?}"))
+}
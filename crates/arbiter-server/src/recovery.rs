@@ -0,0 +1,86 @@
+use arbiter_contracts::PanicReport;
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use futures_util::FutureExt;
+use serde_json::json;
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::clock::Clock;
+use crate::errors::{into_error, ApiFailure};
+use crate::store::AppState;
+
+/// Counts caught panics for `GET /v1/admin/panics`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PanicLog {
+    report: Arc<Mutex<PanicReport>>,
+}
+
+impl PanicLog {
+    /// Records a caught panic under a fresh request id, logs it, and returns
+    /// the `internal_error` that hands the id to the caller.
+    pub(crate) fn record(
+        &self,
+        clock: &Clock,
+        context: &str,
+        payload: &(dyn Any + Send),
+    ) -> ApiFailure {
+        let request_id = clock.id("req");
+        let message = panic_message(payload);
+        eprintln!("panic in {context} (request_id={request_id}): {message}");
+
+        let mut report = self.lock();
+        report.total += 1;
+        report.last_request_id = Some(request_id.clone());
+        report.last_at = Some(clock.now().to_rfc3339());
+        report.last_context = Some(context.to_string());
+        report.last_message = Some(message);
+        ApiFailure::internal(&format!(
+            "{context} panicked; see server log for {request_id}"
+        ))
+        .with_details(json!({ "request_id": request_id }))
+    }
+
+    pub(crate) fn report(&self) -> PanicReport {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PanicReport> {
+        self.report
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Outermost layer: a handler that panics answers `500 internal_error`
+/// instead of dropping the connection. Panics inside store work are caught
+/// earlier by `AppState::with_store`, which still holds the store lock and
+/// can repair the backend before releasing it.
+pub(crate) async fn recover_panics(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let context = format!("{} {}", request.method(), request.uri().path());
+    match AssertUnwindSafe(next.run(request)).catch_unwind().await {
+        Ok(response) => response,
+        Err(payload) => into_error(state.panics().record(
+            state.clock(),
+            &context,
+            payload.as_ref(),
+        ))
+        .into_response(),
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        return message.to_string();
+    }
+    if let Some(message) = payload.downcast_ref::<String>() {
+        return message.clone();
+    }
+    "non-string panic payload".to_string()
+}
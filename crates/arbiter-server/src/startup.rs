@@ -0,0 +1,48 @@
+use arbiter_config::Config;
+use arbiter_contracts::{AuditHead, StartupReport, StartupStoreReport};
+use serde_json::Value;
+
+use crate::audit::read_audit_head;
+use crate::store::AppState;
+
+/// Config values that may embed credentials and are never echoed back.
+const REDACTED_CONFIG_KEYS: [(&str, &str); 1] = [("audit", "checkpoint_publish_url")];
+
+pub(crate) async fn build_startup_report(
+    cfg: &Config,
+    state: &AppState,
+    background_tasks: Vec<String>,
+) -> Result<StartupReport, String> {
+    let mut config =
+        serde_json::to_value(cfg).map_err(|err| format!("failed to encode config: {err}"))?;
+    for (section, key) in REDACTED_CONFIG_KEYS {
+        if let Some(value) = config.get_mut(section).and_then(|v| v.get_mut(key)) {
+            if !value.is_null() {
+                *value = Value::String("[redacted]".to_string());
+            }
+        }
+    }
+
+    let store = state.lock_store().await;
+    let audit_head = match store.audit_path() {
+        Some(path) => {
+            read_audit_head(path).map_err(|err| format!("failed to read audit head: {err:?}"))?
+        }
+        None => AuditHead {
+            records: 0,
+            hash: String::new(),
+        },
+    };
+    Ok(StartupReport {
+        arbiter_version: env!("CARGO_PKG_VERSION").to_string(),
+        started_at: state.clock().now().to_rfc3339(),
+        config,
+        store: StartupStoreReport {
+            kind: store.kind().to_string(),
+            schema_version: store.schema_version(),
+        },
+        contracts: state.contracts_metadata(),
+        background_tasks,
+        audit_head,
+    })
+}
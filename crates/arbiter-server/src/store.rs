@@ -1,16 +1,29 @@
 use arbiter_config::Config;
-use arbiter_contracts::{ContractsMetadata, RunEnvelope};
+use arbiter_contracts::{
+    ArchivedRun, ComponentHealth, ContractsMetadata, RunEnvelope, StartupReport, StoreSnapshot,
+    StoreStats,
+};
 use arbiter_kernel::policy::{ApproverResolverConfig, PolicyConfig};
+use arbiter_kernel::HashAlg;
+use arbiter_store::{
+    export_snapshot, import_snapshot, is_idempotency_expired, IdMatch, IdempotencyRecord,
+    MemoryStore, SqliteStore, Store,
+};
 use chrono::{DateTime, Duration, Utc};
-use rusqlite::{params, Connection};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::json;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration as StdDuration;
 use tokio::sync::Mutex;
 
-use crate::audit::{append_audit_record, read_audit_tail_hash, AuditRecord};
+use crate::audit::{append_audit_record, record_alg, AuditRecord, AuditSink, AUDIT_REANCHOR_EVENT};
+use crate::clock::Clock;
 use crate::contracts::build_contracts_metadata;
+use crate::delay::store_delay;
 use crate::errors::ApiFailure;
+use crate::recovery::PanicLog;
+use crate::tasks::TaskRegistry;
 
 #[derive(Clone)]
 pub(crate) struct AppState {
@@ -18,57 +31,93 @@ pub(crate) struct AppState {
     contracts_metadata: Arc<ContractsMetadata>,
     policy_config: Arc<PolicyConfig>,
     approver_config: Arc<ApproverResolverConfig>,
-    permit_ttl_seconds: u64,
+    permit_ttl: StdDuration,
+    backup_dir: Option<String>,
+    audit_checkpoint_interval: u64,
+    startup_report: Option<Arc<StartupReport>>,
+    read_only: Arc<AtomicBool>,
+    clock: Clock,
+    tasks: TaskRegistry,
+    panics: PanicLog,
+}
+
+pub(crate) fn policy_config_from(cfg: &Config) -> PolicyConfig {
+    PolicyConfig {
+        allowed_providers: cfg.governance.allowed_providers.clone(),
+        capability_allowlist: cfg.governance.capability_allowlist.clone(),
+        capability_denylist: cfg.governance.capability_denylist.clone(),
+        require_approval_for_write_external: cfg.policy.require_approval_for_write_external,
+        require_approval_for_notify: cfg.policy.require_approval_for_notify,
+        require_approval_for_start_job: cfg.policy.require_approval_for_start_job,
+        require_approval_for_production: cfg.policy.require_approval_for_production,
+        warn_only_rules: cfg.policy.warn_only_rules.clone(),
+    }
+}
+
+pub(crate) fn approver_config_from(cfg: &Config) -> ApproverResolverConfig {
+    ApproverResolverConfig {
+        default_approvers: cfg.approver.default_approvers.clone(),
+        production_approvers: cfg.approver.production_approvers.clone(),
+    }
 }
 
 impl AppState {
     pub(crate) fn new(cfg: Config) -> Result<Self, String> {
+        let clock = Clock::from_config(cfg.determinism.as_ref())?;
+        let store = open_store(&cfg, &clock)?;
+        Self::with_backend(cfg, store, clock)
+    }
+
+    /// Like `new`, but opens the sqlite store without migrating it, for
+    /// commands that read a store another process holds the lock on.
+    pub(crate) fn without_migrating(cfg: Config) -> Result<Self, String> {
+        let clock = Clock::from_config(cfg.determinism.as_ref())?;
+        let sqlite_path = cfg
+            .store
+            .sqlite_path
+            .as_deref()
+            .ok_or_else(|| "sqlite_path is required".to_string())?;
+        let store = SqliteStore::open_without_migrating(sqlite_path, &cfg.store.sqlite)?;
+        Self::with_backend(cfg, Box::new(store), clock)
+    }
+
+    pub(crate) fn with_backend(
+        cfg: Config,
+        store: Box<dyn Store>,
+        clock: Clock,
+    ) -> Result<Self, String> {
         let contracts_metadata = build_contracts_metadata();
-        let last_hash =
-            read_audit_tail_hash(&cfg.audit.jsonl_path).map_err(|err| format!("{err:?}"))?;
-
-        let backend = if cfg.store.kind == "sqlite" {
-            let sqlite_path = cfg
-                .store
-                .sqlite_path
-                .clone()
-                .ok_or_else(|| "sqlite_path is required".to_string())?;
-            StoreBackend::Sqlite(SqliteStore::new(
-                &sqlite_path,
-                cfg.audit.jsonl_path.clone(),
-                cfg.audit.immutable_mirror_path.clone(),
-                last_hash,
-                cfg.governance.idempotency_retention_hours,
-            )?)
-        } else {
-            StoreBackend::Memory(MemoryStore {
-                runs: HashMap::new(),
-                approvals: HashMap::new(),
-                idempotency: HashMap::new(),
-                audit_last_hash: last_hash,
-                audit_path: cfg.audit.jsonl_path.clone(),
-                audit_mirror_path: cfg.audit.immutable_mirror_path.clone(),
-                idempotency_retention_hours: cfg.governance.idempotency_retention_hours,
-            })
+        let audit_alg = HashAlg::parse(&cfg.audit.hash_alg)
+            .ok_or_else(|| format!("unsupported audit.hash_alg {}", cfg.audit.hash_alg))?;
+        let audit_sink = AuditSink::from_config(&cfg.audit);
+        let last_hash = audit_sink
+            .read_tail()
+            .map_err(|err| format!("{err:?}"))?
+            .map(|event| event.hash)
+            .unwrap_or_default();
+        let backend = StoreBackend {
+            store,
+            audit_last_hash: last_hash,
+            audit_sink,
+            idempotency_retention: cfg.governance.idempotency_retention,
+            retain_request_payloads: cfg.store.retain_request_payloads,
+            audit_alg,
+            clock: clock.clone(),
         };
 
         Ok(Self {
             store: Arc::new(Mutex::new(backend)),
             contracts_metadata: Arc::new(contracts_metadata),
-            policy_config: Arc::new(PolicyConfig {
-                allowed_providers: cfg.governance.allowed_providers.clone(),
-                capability_allowlist: cfg.governance.capability_allowlist.clone(),
-                capability_denylist: cfg.governance.capability_denylist.clone(),
-                require_approval_for_write_external: cfg.policy.require_approval_for_write_external,
-                require_approval_for_notify: cfg.policy.require_approval_for_notify,
-                require_approval_for_start_job: cfg.policy.require_approval_for_start_job,
-                require_approval_for_production: cfg.policy.require_approval_for_production,
-            }),
-            approver_config: Arc::new(ApproverResolverConfig {
-                default_approvers: cfg.approver.default_approvers,
-                production_approvers: cfg.approver.production_approvers,
-            }),
-            permit_ttl_seconds: cfg.governance.permit_ttl_seconds,
+            policy_config: Arc::new(policy_config_from(&cfg)),
+            approver_config: Arc::new(approver_config_from(&cfg)),
+            permit_ttl: cfg.governance.permit_ttl,
+            backup_dir: cfg.store.backup_dir,
+            audit_checkpoint_interval: cfg.audit.checkpoint_interval,
+            startup_report: None,
+            read_only: Arc::new(AtomicBool::new(cfg.server.read_only)),
+            clock,
+            tasks: TaskRegistry::default(),
+            panics: PanicLog::default(),
         })
     }
 
@@ -76,6 +125,37 @@ impl AppState {
         self.store.lock().await
     }
 
+    /// Runs `f` with exclusive store access on the blocking thread pool, so
+    /// SQLite queries and audit file IO never stall the async executor.
+    /// Request paths use this; `lock_store` is for startup and CLI code.
+    /// A panic in `f` is caught while the lock is still held, the audit
+    /// chain head is re-read from disk, and the panic is reported as an
+    /// `internal_error` carrying a request id.
+    pub(crate) async fn with_store<T, F>(&self, f: F) -> Result<T, ApiFailure>
+    where
+        F: FnOnce(&mut StoreBackend) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let hold = store_delay();
+        let mut store = self.store.clone().lock_owned().await;
+        let outcome = tokio::task::spawn_blocking(move || {
+            if let Some(hold) = hold {
+                std::thread::sleep(hold);
+            }
+            std::panic::catch_unwind(AssertUnwindSafe(|| f(&mut store))).inspect_err(|_| {
+                if let Err(err) = store.reload_audit_head() {
+                    eprintln!("failed to reload audit head after panic: {err:?}");
+                }
+            })
+        })
+        .await
+        .map_err(|err| ApiFailure::internal(&format!("store task failed: {err}")))?;
+        outcome.map_err(|payload| {
+            self.panics
+                .record(&self.clock, "store task", payload.as_ref())
+        })
+    }
+
     pub(crate) fn contracts_metadata(&self) -> ContractsMetadata {
         (*self.contracts_metadata).clone()
     }
@@ -88,53 +168,177 @@ impl AppState {
         self.approver_config.as_ref()
     }
 
-    pub(crate) fn permit_ttl_seconds(&self) -> u64 {
-        self.permit_ttl_seconds
+    pub(crate) fn permit_ttl(&self) -> StdDuration {
+        self.permit_ttl
+    }
+
+    pub(crate) fn backup_dir(&self) -> Option<&str> {
+        self.backup_dir.as_deref()
+    }
+
+    pub(crate) fn audit_checkpoint_interval(&self) -> u64 {
+        self.audit_checkpoint_interval
+    }
+
+    pub(crate) fn with_startup_report(mut self, report: StartupReport) -> Self {
+        self.startup_report = Some(Arc::new(report));
+        self
+    }
+
+    pub(crate) fn startup_report(&self) -> Option<&StartupReport> {
+        self.startup_report.as_deref()
+    }
+
+    pub(crate) fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    pub(crate) fn tasks(&self) -> &TaskRegistry {
+        &self.tasks
+    }
+
+    pub(crate) fn panics(&self) -> &PanicLog {
+        &self.panics
     }
+
+    pub(crate) fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn set_read_only(&self, read_only: bool) {
+        self.read_only.store(read_only, Ordering::SeqCst);
+    }
+}
+
+/// Opens the backend selected by `store.kind`.
+pub(crate) fn open_store(cfg: &Config, clock: &Clock) -> Result<Box<dyn Store>, String> {
+    if cfg.store.kind == "sqlite" {
+        let sqlite_path = cfg
+            .store
+            .sqlite_path
+            .as_deref()
+            .ok_or_else(|| "sqlite_path is required".to_string())?;
+        return Ok(Box::new(SqliteStore::open(sqlite_path, &cfg.store.sqlite)?));
+    }
+    let expired_before = clock.now() - to_chrono(cfg.governance.idempotency_retention);
+    Ok(Box::new(MemoryStore::new(
+        cfg.store.idempotency_journal_path.as_deref(),
+        expired_before,
+        cfg.store
+            .memory
+            .max_idempotency_entries
+            .map(|max| max as usize),
+        cfg.store.memory.max_runs.map(|max| max as usize),
+    )?))
+}
+
+/// Event type of the record listing idempotency keys the store evicted to
+/// stay within `store.memory.max_idempotency_entries`. A retry with an
+/// evicted key is treated as a new request.
+const IDEMPOTENCY_EVICTED_EVENT: &str = "idempotency_evicted";
+
+/// Event type of the record listing archived runs the store evicted to stay
+/// within `store.memory.max_runs`. Their ids no longer resolve.
+const RUNS_EVICTED_EVENT: &str = "archived_runs_evicted";
+
+/// Event type of the record written when a store snapshot is imported.
+const STORE_IMPORTED_EVENT: &str = "store_imported";
+
+/// Event type of the record written when `PUT /v1/admin/read-only` changes
+/// the mode.
+const READ_ONLY_SET_EVENT: &str = "read_only_set";
+
+/// Config durations are `std::time::Duration`; the clock and stored
+/// timestamps are chrono.
+pub(crate) fn to_chrono(duration: StdDuration) -> Duration {
+    Duration::from_std(duration).unwrap_or(Duration::MAX)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct IdempotencyRecord {
-    pub payload_hash: String,
-    pub response_json: String,
-    pub created_at: String,
+fn component_health(name: &str, outcome: Result<(), String>) -> ComponentHealth {
+    ComponentHealth {
+        name: name.to_string(),
+        ready: outcome.is_ok(),
+        detail: outcome.err(),
+    }
 }
 
-pub(crate) enum StoreBackend {
-    Memory(MemoryStore),
-    Sqlite(SqliteStore),
+/// The configured [`Store`] plus the audit chain head. Both sit behind the
+/// one store lock, so a state change and its audit record are written by
+/// the same request without interleaving.
+pub(crate) struct StoreBackend {
+    store: Box<dyn Store>,
+    audit_last_hash: String,
+    audit_sink: AuditSink,
+    idempotency_retention: StdDuration,
+    retain_request_payloads: bool,
+    audit_alg: HashAlg,
+    clock: Clock,
+}
+
+/// An idempotent request body: the hash replays are compared by, and the
+/// canonical JSON it was taken over.
+pub(crate) struct RequestPayload {
+    pub hash: String,
+    pub canonical_json: String,
 }
 
 impl StoreBackend {
+    pub(crate) fn kind(&self) -> &str {
+        self.store.kind()
+    }
+
     pub(crate) fn get_run(&self, run_id: &str) -> Result<Option<RunEnvelope>, ApiFailure> {
-        match self {
-            StoreBackend::Memory(v) => Ok(v.runs.get(run_id).cloned()),
-            StoreBackend::Sqlite(v) => v.get_run(run_id),
-        }
+        Ok(self.store.get_run(run_id)?)
     }
 
     pub(crate) fn put_run(&mut self, run: RunEnvelope) -> Result<(), ApiFailure> {
-        match self {
-            StoreBackend::Memory(v) => {
-                v.runs.insert(run.run.run_id.clone(), run);
-                Ok(())
-            }
-            StoreBackend::Sqlite(v) => v.put_run(run),
-        }
+        let evicted = self.store.put_run(run)?;
+        self.audit_runs_evicted(evicted)
     }
 
     pub(crate) fn find_run_by_request_id(
         &self,
         request_id: &str,
     ) -> Result<Option<RunEnvelope>, ApiFailure> {
-        match self {
-            StoreBackend::Memory(v) => Ok(v
-                .runs
-                .values()
-                .find(|r| r.run.request_id == request_id)
-                .cloned()),
-            StoreBackend::Sqlite(v) => v.find_run_by_request_id(request_id),
+        Ok(self.store.find_run_by_request_id(request_id)?)
+    }
+
+    pub(crate) fn get_archived_run(&self, run_id: &str) -> Result<Option<RunEnvelope>, ApiFailure> {
+        Ok(self.store.get_archived_run(run_id)?)
+    }
+
+    pub(crate) fn lookup_id(&self, id: &str) -> Result<Vec<IdMatch>, ApiFailure> {
+        Ok(self.store.lookup_id(id)?)
+    }
+
+    /// Moves up to `limit` terminal runs last updated before `threshold` out
+    /// of the hot run table. Returns the number of runs archived.
+    pub(crate) fn archive_terminal_runs(
+        &mut self,
+        threshold: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<usize, ApiFailure> {
+        let archived_at = self.clock.now();
+        Ok(self
+            .store
+            .archive_terminal_runs(threshold, archived_at, limit)?)
+    }
+
+    /// Deletes up to `limit` idempotency records older than the retention
+    /// window. Returns the number of records removed.
+    pub(crate) fn prune_expired_idempotency(&mut self, limit: usize) -> Result<usize, ApiFailure> {
+        let expired_before = self.idempotency_expired_before();
+        Ok(self.store.prune_idempotency(expired_before, limit)?)
+    }
+
+    /// One batch of the vacuum task: releases up to `limit` free pages, and
+    /// refreshes planner statistics once nothing is left to release.
+    pub(crate) fn vacuum(&mut self, limit: usize) -> Result<usize, ApiFailure> {
+        let released = self.store.vacuum(limit)?;
+        if released < limit {
+            self.store.analyze()?;
         }
+        Ok(released)
     }
 
     pub(crate) fn map_approval_to_run(
@@ -142,313 +346,258 @@ impl StoreBackend {
         approval_id: &str,
         run_id: &str,
     ) -> Result<(), ApiFailure> {
-        match self {
-            StoreBackend::Memory(v) => {
-                v.approvals
-                    .insert(approval_id.to_string(), run_id.to_string());
-                Ok(())
-            }
-            StoreBackend::Sqlite(v) => v.map_approval_to_run(approval_id, run_id),
-        }
+        Ok(self.store.map_approval_to_run(approval_id, run_id)?)
     }
 
     pub(crate) fn run_id_for_approval(
         &self,
         approval_id: &str,
     ) -> Result<Option<String>, ApiFailure> {
-        match self {
-            StoreBackend::Memory(v) => Ok(v.approvals.get(approval_id).cloned()),
-            StoreBackend::Sqlite(v) => v.run_id_for_approval(approval_id),
-        }
+        Ok(self.store.run_id_for_approval(approval_id)?)
+    }
+
+    pub(crate) fn remove_approval_mapping(&mut self, approval_id: &str) -> Result<(), ApiFailure> {
+        Ok(self.store.remove_approval_mapping(approval_id)?)
+    }
+
+    pub(crate) fn list_runs(&self) -> Result<Vec<RunEnvelope>, ApiFailure> {
+        Ok(self.store.list_runs()?)
+    }
+
+    pub(crate) fn list_approval_mappings(&self) -> Result<Vec<(String, String)>, ApiFailure> {
+        Ok(self.store.list_approval_mappings()?)
+    }
+
+    pub(crate) fn list_archived_runs(&self) -> Result<Vec<ArchivedRun>, ApiFailure> {
+        Ok(self.store.list_archived_runs()?)
     }
 
+    /// Lists every idempotency record, including ones past the retention
+    /// window that the sweep has not pruned yet.
+    pub(crate) fn list_idempotency(&self) -> Result<Vec<(String, IdempotencyRecord)>, ApiFailure> {
+        Ok(self.store.list_idempotency()?)
+    }
+
+    pub(crate) fn schema_version(&self) -> Option<i64> {
+        self.store.schema_version()
+    }
+
+    pub(crate) fn supported_schema_version(&self) -> Option<i64> {
+        self.store.supported_schema_version()
+    }
+
+    /// Returns the record for `key` unless it is past the retention window.
     pub(crate) fn get_idempotency(
-        &mut self,
+        &self,
         key: &str,
     ) -> Result<Option<IdempotencyRecord>, ApiFailure> {
-        match self {
-            StoreBackend::Memory(v) => {
-                if let Some(entry) = v.idempotency.get(key).cloned() {
-                    if is_idempotency_expired(&entry.created_at, v.idempotency_retention_hours) {
-                        v.idempotency.remove(key);
-                        return Ok(None);
-                    }
-                    return Ok(Some(entry));
-                }
-                Ok(None)
-            }
-            StoreBackend::Sqlite(v) => v.get_idempotency(key),
-        }
+        let expired_before = self.idempotency_expired_before();
+        Ok(self
+            .store
+            .get_idempotency(key)?
+            .filter(|record| !is_idempotency_expired(&record.created_at, expired_before)))
     }
 
     pub(crate) fn put_idempotency(
         &mut self,
         key: &str,
-        payload_hash: &str,
+        payload: &RequestPayload,
         response_json: &str,
     ) -> Result<(), ApiFailure> {
-        match self {
-            StoreBackend::Memory(v) => {
-                v.idempotency.insert(
-                    key.to_string(),
-                    IdempotencyRecord {
-                        payload_hash: payload_hash.to_string(),
-                        response_json: response_json.to_string(),
-                        created_at: Utc::now().to_rfc3339(),
-                    },
-                );
-                Ok(())
-            }
-            StoreBackend::Sqlite(v) => v.put_idempotency(key, payload_hash, response_json),
-        }
+        let record = IdempotencyRecord {
+            payload_hash: payload.hash.clone(),
+            response_json: response_json.to_string(),
+            created_at: self.clock.now().to_rfc3339(),
+            payload_json: self
+                .retain_request_payloads
+                .then(|| payload.canonical_json.clone()),
+        };
+        let expired_before = self.idempotency_expired_before();
+        let evicted = self.store.put_idempotency(key, record, expired_before)?;
+        self.audit_idempotency_evicted(evicted)
     }
 
-    pub(crate) fn append_audit(&mut self, record: AuditRecord) -> Result<(), ApiFailure> {
-        match self {
-            StoreBackend::Memory(v) => {
-                let event = append_audit_record(
-                    &v.audit_path,
-                    v.audit_mirror_path.as_deref(),
-                    &v.audit_last_hash,
-                    record,
-                )?;
-                v.audit_last_hash = event.hash;
-                Ok(())
-            }
-            StoreBackend::Sqlite(v) => v.append_audit(record),
+    fn audit_idempotency_evicted(&mut self, evicted: Vec<String>) -> Result<(), ApiFailure> {
+        if evicted.is_empty() {
+            return Ok(());
         }
+        let rationale = format!(
+            "store.memory.max_idempotency_entries reached; evicted {}",
+            evicted.join(", ")
+        );
+        let mut record = AuditRecord::new(
+            IDEMPOTENCY_EVICTED_EVENT,
+            "",
+            "arbiter",
+            json!({ "keys": evicted }),
+        );
+        record.rationale = Some(rationale);
+        self.append_audit(record)
     }
 
-    pub(crate) fn audit_path(&self) -> &str {
-        match self {
-            StoreBackend::Memory(v) => &v.audit_path,
-            StoreBackend::Sqlite(v) => &v.audit_path,
+    fn audit_runs_evicted(&mut self, evicted: Vec<String>) -> Result<(), ApiFailure> {
+        if evicted.is_empty() {
+            return Ok(());
         }
+        let rationale = format!(
+            "store.memory.max_runs reached; evicted {}",
+            evicted.join(", ")
+        );
+        let mut record = AuditRecord::new(
+            RUNS_EVICTED_EVENT,
+            "",
+            "arbiter",
+            json!({ "run_ids": evicted }),
+        );
+        record.rationale = Some(rationale);
+        self.append_audit(record)
     }
 
-    pub(crate) fn doctor(&self) -> Result<Vec<String>, ApiFailure> {
-        match self {
-            StoreBackend::Memory(v) => Ok(vec![
-                "store=memory".to_string(),
-                format!("runs={}", v.runs.len()),
-                format!("idempotency_records={}", v.idempotency.len()),
-            ]),
-            StoreBackend::Sqlite(v) => v.doctor(),
-        }
+    fn idempotency_expired_before(&self) -> DateTime<Utc> {
+        self.clock.now() - to_chrono(self.idempotency_retention)
     }
-}
-
-pub(crate) struct MemoryStore {
-    runs: HashMap<String, RunEnvelope>,
-    approvals: HashMap<String, String>,
-    idempotency: HashMap<String, IdempotencyRecord>,
-    audit_last_hash: String,
-    audit_path: String,
-    audit_mirror_path: Option<String>,
-    idempotency_retention_hours: u64,
-}
 
-pub(crate) struct SqliteStore {
-    conn: Connection,
-    audit_last_hash: String,
-    audit_path: String,
-    audit_mirror_path: Option<String>,
-    idempotency_retention_hours: u64,
-}
+    /// Appends an `audit_reanchored` record when the configured algorithm
+    /// differs from the one used by the last record, so the switch is
+    /// explicit in the chain. Returns whether a record was written.
+    pub(crate) fn reanchor_audit_if_needed(&mut self) -> Result<bool, ApiFailure> {
+        let Some(tail) = self.audit_sink.read_tail()? else {
+            return Ok(false);
+        };
+        let tail_alg = record_alg(&tail).map_err(|err| ApiFailure::internal(&err))?;
+        if tail_alg == self.audit_alg {
+            return Ok(false);
+        }
+        let mut record = AuditRecord::new(
+            AUDIT_REANCHOR_EVENT,
+            "",
+            "arbiter",
+            json!({
+                "from_alg": tail_alg.as_str(),
+                "to_alg": self.audit_alg.as_str(),
+                "anchor_hash": tail.hash,
+            }),
+        );
+        record.rationale = Some(format!(
+            "audit hash algorithm changed from {} to {}",
+            tail_alg.as_str(),
+            self.audit_alg.as_str()
+        ));
+        self.append_audit(record)?;
+        Ok(true)
+    }
 
-impl SqliteStore {
-    fn new(
-        sqlite_path: &str,
-        audit_path: String,
-        audit_mirror_path: Option<String>,
-        audit_last_hash: String,
-        idempotency_retention_hours: u64,
-    ) -> Result<Self, String> {
-        let conn = Connection::open(sqlite_path)
-            .map_err(|err| format!("failed to open sqlite database: {err}"))?;
-        conn.execute_batch(
-            "
-            CREATE TABLE IF NOT EXISTS runs (
-                run_id TEXT PRIMARY KEY,
-                request_id TEXT UNIQUE NOT NULL,
-                envelope_json TEXT NOT NULL
-            );
-            CREATE TABLE IF NOT EXISTS approvals (
-                approval_id TEXT PRIMARY KEY,
-                run_id TEXT NOT NULL
-            );
-            CREATE TABLE IF NOT EXISTS idempotency (
-                idem_key TEXT PRIMARY KEY,
-                payload_hash TEXT NOT NULL,
-                response_json TEXT NOT NULL,
-                created_at TEXT NOT NULL
-            );
-            ",
-        )
-        .map_err(|err| format!("failed to initialize sqlite schema: {err}"))?;
-        Ok(Self {
-            conn,
-            audit_last_hash,
-            audit_path,
-            audit_mirror_path,
-            idempotency_retention_hours,
-        })
+    pub(crate) fn append_audit(&mut self, record: AuditRecord) -> Result<(), ApiFailure> {
+        append_audit_record(
+            &self.audit_sink,
+            &mut self.audit_last_hash,
+            record,
+            self.audit_alg,
+            &self.clock,
+        )?;
+        Ok(())
     }
 
-    fn get_run(&self, run_id: &str) -> Result<Option<RunEnvelope>, ApiFailure> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT envelope_json FROM runs WHERE run_id = ?1")
-            .map_err(|err| ApiFailure::internal(&err.to_string()))?;
-        let row = stmt
-            .query_row(params![run_id], |row| row.get::<_, String>(0))
-            .ok();
-        let Some(text) = row else {
-            return Ok(None);
-        };
-        let run: RunEnvelope =
-            serde_json::from_str(&text).map_err(|err| ApiFailure::internal(&err.to_string()))?;
-        Ok(Some(run))
-    }
-
-    fn put_run(&mut self, run: RunEnvelope) -> Result<(), ApiFailure> {
-        let run_id = run.run.run_id.clone();
-        let request_id = run.run.request_id.clone();
-        let json =
-            serde_json::to_string(&run).map_err(|err| ApiFailure::internal(&err.to_string()))?;
-        self.conn
-            .execute(
-                "INSERT INTO runs (run_id, request_id, envelope_json) VALUES (?1, ?2, ?3)
-                 ON CONFLICT(run_id) DO UPDATE SET request_id=excluded.request_id, envelope_json=excluded.envelope_json",
-                params![run_id, request_id, json],
-            )
-            .map_err(|err| ApiFailure::internal(&err.to_string()))?;
+    /// Re-reads the chain head from the audit file, so a record written just
+    /// before a panic is chained to rather than forked from. A stdout sink
+    /// has nothing to re-read and keeps the head it holds.
+    pub(crate) fn reload_audit_head(&mut self) -> Result<(), ApiFailure> {
+        if self.audit_sink.path().is_none() {
+            return Ok(());
+        }
+        self.audit_last_hash = self
+            .audit_sink
+            .read_tail()?
+            .map(|event| event.hash)
+            .unwrap_or_default();
         Ok(())
     }
 
-    fn find_run_by_request_id(&self, request_id: &str) -> Result<Option<RunEnvelope>, ApiFailure> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT envelope_json FROM runs WHERE request_id = ?1")
-            .map_err(|err| ApiFailure::internal(&err.to_string()))?;
-        let row = stmt
-            .query_row(params![request_id], |row| row.get::<_, String>(0))
-            .ok();
-        let Some(text) = row else {
-            return Ok(None);
-        };
-        let run: RunEnvelope =
-            serde_json::from_str(&text).map_err(|err| ApiFailure::internal(&err.to_string()))?;
-        Ok(Some(run))
+    /// Components reported by `GET /v1/readyz`: the store backend, the
+    /// audit log, and the mirror and fan-out outputs a failed write would
+    /// fail requests for. Stdout is reported ready; a failed write surfaces
+    /// on the request that made it.
+    pub(crate) fn readiness(&self) -> Vec<ComponentHealth> {
+        let mut components = vec![
+            component_health("store", self.store.ping().map_err(|err| err.to_string())),
+            component_health("audit", self.audit_sink.primary().check_writable()),
+        ];
+        for fanout in self.audit_sink.fanout() {
+            if !fanout.best_effort {
+                components.push(component_health(
+                    &fanout.name,
+                    fanout.output.check_writable(),
+                ));
+            }
+        }
+        components
+    }
+
+    /// The audit log file, or `None` when `audit.sink=stdout`.
+    pub(crate) fn audit_path(&self) -> Option<&str> {
+        self.audit_sink.path()
     }
 
-    fn map_approval_to_run(&mut self, approval_id: &str, run_id: &str) -> Result<(), ApiFailure> {
-        self.conn
-            .execute(
-                "INSERT INTO approvals (approval_id, run_id) VALUES (?1, ?2)
-                 ON CONFLICT(approval_id) DO UPDATE SET run_id=excluded.run_id",
-                params![approval_id, run_id],
+    /// The audit log file for endpoints that read records back; `409` when
+    /// `audit.sink=stdout` keeps none.
+    pub(crate) fn audit_log(&self) -> Result<&str, ApiFailure> {
+        self.audit_path().ok_or_else(|| {
+            ApiFailure::conflict(
+                "audit_not_readable",
+                "audit.sink=stdout keeps no audit records in the server",
             )
-            .map_err(|err| ApiFailure::internal(&err.to_string()))?;
-        Ok(())
+        })
     }
 
-    fn run_id_for_approval(&self, approval_id: &str) -> Result<Option<String>, ApiFailure> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT run_id FROM approvals WHERE approval_id = ?1")
-            .map_err(|err| ApiFailure::internal(&err.to_string()))?;
-        let row = stmt
-            .query_row(params![approval_id], |row| row.get::<_, String>(0))
-            .ok();
-        Ok(row)
-    }
-
-    fn get_idempotency(&mut self, key: &str) -> Result<Option<IdempotencyRecord>, ApiFailure> {
-        self.cleanup_expired_idempotency()?;
-        let mut stmt = self.conn.prepare(
-            "SELECT payload_hash, response_json, created_at FROM idempotency WHERE idem_key = ?1",
-        )
-        .map_err(|err| ApiFailure::internal(&err.to_string()))?;
-        let row = stmt
-            .query_row(params![key], |row| {
-                Ok(IdempotencyRecord {
-                    payload_hash: row.get(0)?,
-                    response_json: row.get(1)?,
-                    created_at: row.get(2)?,
-                })
-            })
-            .ok();
-        Ok(row)
+    pub(crate) fn backup(&self, out_path: &str) -> Result<(), ApiFailure> {
+        Ok(self.store.backup(out_path)?)
     }
 
-    fn put_idempotency(
-        &mut self,
-        key: &str,
-        payload_hash: &str,
-        response_json: &str,
-    ) -> Result<(), ApiFailure> {
-        self.cleanup_expired_idempotency()?;
-        self.conn
-            .execute(
-                "INSERT INTO idempotency (idem_key, payload_hash, response_json, created_at)
-                 VALUES (?1, ?2, ?3, ?4)
-                 ON CONFLICT(idem_key) DO NOTHING",
-                params![key, payload_hash, response_json, Utc::now().to_rfc3339()],
-            )
-            .map_err(|err| ApiFailure::internal(&err.to_string()))?;
-        Ok(())
+    pub(crate) fn export_snapshot(&self) -> Result<StoreSnapshot, ApiFailure> {
+        Ok(export_snapshot(self.store.as_ref())?)
     }
 
-    fn cleanup_expired_idempotency(&mut self) -> Result<(), ApiFailure> {
-        let threshold =
-            (Utc::now() - Duration::hours(self.idempotency_retention_hours as i64)).to_rfc3339();
-        self.conn
-            .execute(
-                "DELETE FROM idempotency WHERE created_at < ?1",
-                params![threshold],
-            )
-            .map_err(|err| ApiFailure::internal(&err.to_string()))?;
-        Ok(())
+    /// Seeds the (empty) store from `snapshot` and records a
+    /// `store_imported` audit event with what was loaded.
+    pub(crate) fn import_snapshot(&mut self, snapshot: StoreSnapshot) -> Result<(), ApiFailure> {
+        let payload = json!({
+            "source_kind": snapshot.source_kind,
+            "runs": snapshot.runs.len(),
+            "archived_runs": snapshot.archived_runs.len(),
+            "approval_mappings": snapshot.approval_mappings.len(),
+            "idempotency_records": snapshot.idempotency_records.len(),
+        });
+        let rationale = format!(
+            "imported store snapshot taken from store.kind={}",
+            snapshot.source_kind
+        );
+        let evicted = import_snapshot(self.store.as_mut(), snapshot)?;
+        let mut record = AuditRecord::new(STORE_IMPORTED_EVENT, "", "arbiter", payload);
+        record.rationale = Some(rationale);
+        self.append_audit(record)?;
+        self.audit_idempotency_evicted(evicted)
     }
 
-    fn append_audit(&mut self, record: AuditRecord) -> Result<(), ApiFailure> {
-        let event = append_audit_record(
-            &self.audit_path,
-            self.audit_mirror_path.as_deref(),
-            &self.audit_last_hash,
-            record,
-        )?;
-        self.audit_last_hash = event.hash;
-        Ok(())
+    /// Records a change of read-only mode, before the caller applies it.
+    pub(crate) fn record_read_only(&mut self, read_only: bool) -> Result<(), ApiFailure> {
+        let mut record = AuditRecord::new(
+            READ_ONLY_SET_EVENT,
+            "",
+            "arbiter",
+            json!({"read_only": read_only}),
+        );
+        record.rationale = Some(format!(
+            "read-only mode turned {}",
+            if read_only { "on" } else { "off" }
+        ));
+        self.append_audit(record)
     }
 
-    fn doctor(&self) -> Result<Vec<String>, ApiFailure> {
-        let mut out = vec!["store=sqlite".to_string()];
-        let mut stmt = self
-            .conn
-            .prepare("SELECT COUNT(*) FROM runs")
-            .map_err(|err| ApiFailure::internal(&err.to_string()))?;
-        let runs: i64 = stmt
-            .query_row([], |row| row.get(0))
-            .map_err(|err| ApiFailure::internal(&err.to_string()))?;
-        out.push(format!("runs={runs}"));
-        let mut stmt = self
-            .conn
-            .prepare("SELECT COUNT(*) FROM idempotency")
-            .map_err(|err| ApiFailure::internal(&err.to_string()))?;
-        let idem: i64 = stmt
-            .query_row([], |row| row.get(0))
-            .map_err(|err| ApiFailure::internal(&err.to_string()))?;
-        out.push(format!("idempotency_records={idem}"));
-        Ok(out)
+    pub(crate) fn stats(&self) -> Result<StoreStats, ApiFailure> {
+        Ok(self.store.stats()?)
     }
-}
 
-fn is_idempotency_expired(created_at: &str, retention_hours: u64) -> bool {
-    let Ok(ts) = DateTime::parse_from_rfc3339(created_at) else {
-        return false;
-    };
-    let threshold = Utc::now() - Duration::hours(retention_hours as i64);
-    ts.with_timezone(&Utc) < threshold
+    pub(crate) fn doctor(&self) -> Result<Vec<String>, ApiFailure> {
+        Ok(self.store.doctor()?)
+    }
 }
@@ -0,0 +1,136 @@
+use arbiter_contracts::BackgroundTaskStatus;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use crate::errors::ApiFailure;
+use crate::store::{AppState, StoreBackend};
+
+/// Progress of the background tasks, served by `GET /v1/admin/tasks`.
+/// `batches` and `processed` describe the current pass, or the last one
+/// while the task is idle.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TaskRegistry {
+    tasks: Arc<Mutex<BTreeMap<String, BackgroundTaskStatus>>>,
+}
+
+impl TaskRegistry {
+    pub(crate) fn register(&self, name: &str) {
+        self.update(name, |_| {});
+    }
+
+    pub(crate) fn list(&self) -> Vec<BackgroundTaskStatus> {
+        self.lock().values().cloned().collect()
+    }
+
+    fn start(&self, name: &str, started_at: String) {
+        self.update(name, |s| {
+            s.running = true;
+            s.passes += 1;
+            s.last_started_at = Some(started_at);
+            s.batches = 0;
+            s.processed = 0;
+            s.last_error = None;
+        });
+    }
+
+    fn finish(&self, name: &str, finished_at: String, error: Option<String>) {
+        self.update(name, |s| {
+            s.running = false;
+            s.last_finished_at = Some(finished_at);
+            s.last_error = error;
+        });
+    }
+
+    fn update(&self, name: &str, f: impl FnOnce(&mut BackgroundTaskStatus)) {
+        let mut tasks = self.lock();
+        let status = tasks
+            .entry(name.to_string())
+            .or_insert_with(|| BackgroundTaskStatus {
+                name: name.to_string(),
+                running: false,
+                passes: 0,
+                last_started_at: None,
+                last_finished_at: None,
+                batches: 0,
+                processed: 0,
+                total_processed: 0,
+                last_error: None,
+            });
+        f(status);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, BackgroundTaskStatus>> {
+        self.tasks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Runs one pass of `batch` until it handles fewer than `batch_size` rows.
+/// The store lock is released and the task yields between batches, so
+/// requests interleave with a long pass instead of waiting for all of it.
+/// Every batch commits on its own; an interrupted pass loses no work and the
+/// next pass picks up whatever is still eligible.
+pub(crate) async fn run_batched_pass<F>(state: &AppState, name: &str, batch_size: usize, batch: F)
+where
+    F: Fn(&mut StoreBackend, usize) -> Result<usize, ApiFailure> + Clone + Send + 'static,
+{
+    let tasks = state.tasks();
+    tasks.start(name, state.clock().now().to_rfc3339());
+
+    let mut error = None;
+    loop {
+        let run = batch.clone();
+        let handled = state
+            .with_store(move |store| run(store, batch_size))
+            .await
+            .and_then(|handled| handled);
+        match handled {
+            Ok(handled) => {
+                tasks.update(name, |s| {
+                    s.batches += 1;
+                    s.processed += handled as u64;
+                    s.total_processed += handled as u64;
+                });
+                if handled < batch_size {
+                    break;
+                }
+                tokio::task::yield_now().await;
+            }
+            Err(err) => {
+                eprintln!("{name} failed: {err:?}");
+                error = Some(format!("{err:?}"));
+                break;
+            }
+        }
+    }
+
+    tasks.finish(name, state.clock().now().to_rfc3339(), error);
+}
+
+/// Runs one pass of a task that is not batched, such as the audit
+/// uploaders, and records it as a single batch that processed the count
+/// `pass` returns. The error, if any, is recorded and handed back.
+pub(crate) async fn run_pass(
+    state: &AppState,
+    name: &str,
+    pass: impl Future<Output = Result<u64, String>>,
+) -> Result<u64, String> {
+    let tasks = state.tasks();
+    tasks.start(name, state.clock().now().to_rfc3339());
+    let result = pass.await;
+    if let Ok(processed) = result {
+        tasks.update(name, |s| {
+            s.batches = 1;
+            s.processed = processed;
+            s.total_processed += processed;
+        });
+    }
+    tasks.finish(
+        name,
+        state.clock().now().to_rfc3339(),
+        result.as_ref().err().cloned(),
+    );
+    result
+}
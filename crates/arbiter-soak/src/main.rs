@@ -0,0 +1,503 @@
+//! Soak harness: drives randomized operation-request, step, approval and
+//! result traffic against an in-process app for as long as asked, checking
+//! invariants while it runs and once more after the traffic stops.
+//!
+//! `cargo run -p arbiter-soak -- --duration 2h --workers 16 --store sqlite`
+
+use arbiter_contracts::{AuditVerifyResponse, StoreStats};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tower::util::ServiceExt;
+
+const USAGE: &str = "\
+Usage:
+  arbiter-soak [--duration <duration>] [--workers <n>] [--seed <n>]
+               [--store memory|sqlite] [--check-interval <duration>]";
+
+/// Request ids come from a window of `REQUEST_POOL` ids that slides forward
+/// by one every `REQUEST_WINDOW_STEP` requests, and client step ids from a
+/// small fixed pool, so replays of the same key are frequent while new runs
+/// keep arriving for as long as the soak lasts.
+const REQUEST_POOL: u64 = 500;
+const REQUEST_WINDOW_STEP: u64 = 20;
+const STEP_POOL: u64 = 4;
+
+/// Steps, approvals and results target the newest runs, which are the ones
+/// still open.
+const RECENT_RUNS: usize = 1_000;
+
+/// `GET /v1/admin/audit/verify` accepts at most this many records per call.
+const AUDIT_VERIFY_WINDOW: u64 = 10_000;
+
+struct Options {
+    duration: Duration,
+    workers: usize,
+    seed: u64,
+    store: String,
+    check_interval: Duration,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        duration: Duration::from_secs(60),
+        workers: 8,
+        seed: 1,
+        store: "memory".to_string(),
+        check_interval: Duration::from_secs(10),
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--duration" => options.duration = arbiter_config::units::parse_duration(&value()?)?,
+            "--workers" => {
+                options.workers = value()?
+                    .parse()
+                    .map_err(|err| format!("invalid --workers: {err}"))?
+            }
+            "--seed" => {
+                options.seed = value()?
+                    .parse()
+                    .map_err(|err| format!("invalid --seed: {err}"))?
+            }
+            "--store" => options.store = value()?,
+            "--check-interval" => {
+                options.check_interval = arbiter_config::units::parse_duration(&value()?)?
+            }
+            _ => return Err(format!("unknown argument {arg}")),
+        }
+    }
+    if options.workers == 0 {
+        return Err("--workers must be at least 1".to_string());
+    }
+    if options.store != "memory" && options.store != "sqlite" {
+        return Err("--store must be memory|sqlite".to_string());
+    }
+    if options.check_interval.is_zero() {
+        return Err("--check-interval must be greater than 0".to_string());
+    }
+    Ok(options)
+}
+
+/// xorshift64*; enough to spread traffic, and reproducible from `--seed`.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+struct Step {
+    step_id: String,
+    approval_id: Option<String>,
+}
+
+/// Ids the server has handed out, so replays can be checked against them.
+#[derive(Default)]
+struct Known {
+    runs: HashMap<String, String>,
+    run_ids: Vec<String>,
+    steps: HashMap<(String, String), Step>,
+    step_keys: Vec<(String, String)>,
+}
+
+struct Soak {
+    app: Router,
+    known: Mutex<Known>,
+    requests: AtomicU64,
+    stop: AtomicBool,
+}
+
+impl Soak {
+    async fn call(&self, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let mut request = Request::builder().method(method).uri(uri);
+        if body.is_some() {
+            request = request.header("content-type", "application/json");
+        }
+        let request = request
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .expect("valid request");
+        let response = self
+            .app
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("router is infallible");
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap_or_default();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    fn pick_run(&self, rng: &mut Rng) -> Option<String> {
+        let known = self.known.lock().unwrap();
+        let recent = &known.run_ids[known.run_ids.len().saturating_sub(RECENT_RUNS)..];
+        let n = recent.len() as u64;
+        (n > 0).then(|| recent[rng.below(n) as usize].clone())
+    }
+
+    fn pick_step(&self, rng: &mut Rng) -> Option<(String, String, Option<String>)> {
+        let known = self.known.lock().unwrap();
+        let recent = &known.step_keys[known.step_keys.len().saturating_sub(RECENT_RUNS)..];
+        let n = recent.len() as u64;
+        if n == 0 {
+            return None;
+        }
+        let key = &recent[rng.below(n) as usize];
+        let step = &known.steps[key];
+        Some((
+            key.0.clone(),
+            step.step_id.clone(),
+            step.approval_id.clone(),
+        ))
+    }
+
+    async fn create_run(&self, rng: &mut Rng) -> Result<(), String> {
+        let window = self.requests.load(Ordering::Relaxed) / REQUEST_WINDOW_STEP;
+        let n = window + rng.below(REQUEST_POOL);
+        let request_id = format!("soak-req-{n}");
+        let body = json!({
+            "request_id": request_id,
+            "source": "soak",
+            "requester": "soak",
+            "objective": format!("soak objective {n}"),
+            "environment_hint": if n.is_multiple_of(3) { "prod" } else { "dev" },
+            "metadata": {"n": n}
+        });
+        let (status, response) = self
+            .call("POST", "/v1/operation-requests", Some(body))
+            .await;
+        expect_handled(&status, "create run", &response)?;
+        if !status.is_success() {
+            return Ok(());
+        }
+        let run_id = response["run_id"].as_str().unwrap_or_default().to_string();
+        let mut known = self.known.lock().unwrap();
+        match known.runs.get(&request_id) {
+            Some(existing) if *existing != run_id => Err(format!(
+                "replay of {request_id} returned run {run_id}, first returned {existing}"
+            )),
+            Some(_) => Ok(()),
+            None => {
+                known.runs.insert(request_id, run_id.clone());
+                known.run_ids.push(run_id);
+                Ok(())
+            }
+        }
+    }
+
+    async fn declare_step(&self, rng: &mut Rng) -> Result<(), String> {
+        let Some(run_id) = self.pick_run(rng) else {
+            return Ok(());
+        };
+        let n = rng.below(STEP_POOL);
+        let client_step_id = format!("step-{n}");
+        let body = json!({
+            "client_step_id": client_step_id,
+            "intent_type": if n == 3 { "notify" } else { "change" },
+            "capability": "soak",
+            "target": format!("target-{n}"),
+            "risk_level": if n.is_multiple_of(2) { "write" } else { "read" },
+            "provider": "generic",
+            "metadata": {}
+        });
+        let uri = format!("/v1/runs/{run_id}/step-intents");
+        let (status, response) = self.call("POST", &uri, Some(body)).await;
+        expect_handled(&status, "declare step", &response)?;
+        if !status.is_success() {
+            return Ok(());
+        }
+        let step = Step {
+            step_id: response["step_id"].as_str().unwrap_or_default().to_string(),
+            approval_id: response["approval_id"].as_str().map(str::to_string),
+        };
+        let key = (run_id, client_step_id);
+        let mut known = self.known.lock().unwrap();
+        match known.steps.get(&key) {
+            Some(existing) if existing.step_id != step.step_id => Err(format!(
+                "replay of {}/{} returned step {}, first returned {}",
+                key.0, key.1, step.step_id, existing.step_id
+            )),
+            Some(_) => Ok(()),
+            None => {
+                known.step_keys.push(key.clone());
+                known.steps.insert(key, step);
+                Ok(())
+            }
+        }
+    }
+
+    async fn decide_approval(&self, rng: &mut Rng) -> Result<(), String> {
+        let Some((_, _, Some(approval_id))) = self.pick_step(rng) else {
+            return Ok(());
+        };
+        let action = ["grant", "grant", "deny", "cancel"][rng.below(4) as usize];
+        let uri = format!("/v1/approvals/{approval_id}/{action}");
+        let body = json!({"actor": "soak-approver", "reason": "soak"});
+        let (status, response) = self.call("POST", &uri, Some(body)).await;
+        expect_handled(&status, action, &response)
+    }
+
+    async fn submit_result(&self, rng: &mut Rng) -> Result<(), String> {
+        let Some((run_id, step_id, _)) = self.pick_step(rng) else {
+            return Ok(());
+        };
+        // Derived from the step so a replay always sends the same payload.
+        let failed = step_id
+            .bytes()
+            .map(u64::from)
+            .sum::<u64>()
+            .is_multiple_of(5);
+        let body = json!({
+            "step_id": step_id,
+            "execution_result": if failed { "failed" } else { "ok" },
+            "artifacts": {},
+            "error": if failed { json!("soak failure") } else { Value::Null },
+            "executor_metadata": {"executor": "soak"}
+        });
+        let uri = format!("/v1/runs/{run_id}/step-results");
+        let (status, response) = self.call("POST", &uri, Some(body)).await;
+        expect_handled(&status, "submit result", &response)
+    }
+
+    async fn worker(self: Arc<Self>, mut rng: Rng, deadline: Instant) -> Result<(), String> {
+        while Instant::now() < deadline && !self.stop.load(Ordering::Relaxed) {
+            let outcome = match rng.below(10) {
+                0..=2 => self.create_run(&mut rng).await,
+                3..=5 => self.declare_step(&mut rng).await,
+                6..=7 => self.decide_approval(&mut rng).await,
+                _ => self.submit_result(&mut rng).await,
+            };
+            if let Err(err) = outcome {
+                self.stop.store(true, Ordering::Relaxed);
+                return Err(err);
+            }
+            // An action with nothing to pick returns without awaiting.
+            tokio::task::yield_now().await;
+        }
+        Ok(())
+    }
+
+    /// Verifies the newest audit records and a sample of known runs. Returns
+    /// the audit record count.
+    async fn check(&self, rng: &mut Rng) -> Result<u64, String> {
+        let (status, response) = self
+            .call("GET", "/v1/admin/audit/verify?from=1&to=1", None)
+            .await;
+        let total = if status == StatusCode::OK {
+            decode::<AuditVerifyResponse>(response)?.total_records
+        } else {
+            0
+        };
+        if total > 0 {
+            let from = total.saturating_sub(AUDIT_VERIFY_WINDOW - 1).max(1);
+            let uri = format!("/v1/admin/audit/verify?from={from}&to={total}");
+            let (status, response) = self.call("GET", &uri, None).await;
+            if status != StatusCode::OK {
+                return Err(format!("audit verify returned {status}: {response}"));
+            }
+            let report: AuditVerifyResponse = decode(response)?;
+            if !report.valid {
+                return Err(format!(
+                    "audit chain invalid in records {from}..={total}: {}",
+                    report.error.unwrap_or_default()
+                ));
+            }
+        }
+        for _ in 0..10 {
+            let Some(run_id) = self.pick_run(rng) else {
+                break;
+            };
+            let (status, response) = self.call("GET", &format!("/v1/runs/{run_id}"), None).await;
+            if status != StatusCode::OK {
+                return Err(format!("known run {run_id} returned {status}: {response}"));
+            }
+        }
+        Ok(total)
+    }
+
+    /// Runs after the workers stop: every run in the store was seen by a
+    /// worker exactly once, so no replay created a second run.
+    async fn final_check(&self) -> Result<(), String> {
+        let (status, response) = self.call("GET", "/v1/admin/store/stats", None).await;
+        if status != StatusCode::OK {
+            return Err(format!("store stats returned {status}: {response}"));
+        }
+        let stats: StoreStats = decode(response)?;
+        let known = self.known.lock().unwrap().run_ids.len() as u64;
+        if stats.runs + stats.archived_runs != known {
+            return Err(format!(
+                "store holds {} runs, workers were handed {known}",
+                stats.runs + stats.archived_runs
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Every request the harness sends is well formed, so only refusals based
+/// on the current state may come back: `404`, `409`, `423`, or `422` for an
+/// invalid state transition.
+fn expect_handled(status: &StatusCode, action: &str, response: &Value) -> Result<(), String> {
+    let allowed = status.is_success()
+        || [
+            StatusCode::NOT_FOUND,
+            StatusCode::CONFLICT,
+            StatusCode::LOCKED,
+        ]
+        .contains(status)
+        || (*status == StatusCode::UNPROCESSABLE_ENTITY
+            && response["error"]["code"] == "invalid_transition");
+    if allowed {
+        Ok(())
+    } else {
+        Err(format!("{action} returned {status}: {response}"))
+    }
+}
+
+fn decode<T: serde::de::DeserializeOwned>(value: Value) -> Result<T, String> {
+    serde_json::from_value(value).map_err(|err| format!("unexpected response: {err}"))
+}
+
+/// Builds the starter config with the store and audit log in a fresh
+/// directory, which is left in place if the soak fails.
+fn soak_config(options: &Options) -> Result<(arbiter_config::Config, PathBuf), String> {
+    let mut cfg = arbiter_config::parse_and_validate(arbiter_config::STARTER_CONFIG_YAML)
+        .map_err(|err| format!("invalid starter config: {err}"))?;
+    let dir = std::env::temp_dir().join(format!("arbiter-soak-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)
+        .map_err(|err| format!("failed to create {}: {err}", dir.display()))?;
+    cfg.audit.jsonl_path = dir.join("audit.jsonl").to_string_lossy().to_string();
+    cfg.audit.immutable_mirror_path = None;
+    cfg.store.kind = options.store.clone();
+    if options.store == "sqlite" {
+        cfg.store.sqlite_path = Some(dir.join("store.db").to_string_lossy().to_string());
+    }
+    Ok((cfg, dir))
+}
+
+async fn run(options: Options) -> Result<(), String> {
+    let (cfg, dir) = soak_config(&options)?;
+    let audit_path = cfg.audit.jsonl_path.clone();
+    println!(
+        "soak: store={} workers={} seed={} duration={}s audit={audit_path}",
+        options.store,
+        options.workers,
+        options.seed,
+        options.duration.as_secs()
+    );
+    let soak = Arc::new(Soak {
+        app: arbiter_server::build_app(cfg).await?,
+        known: Mutex::default(),
+        requests: AtomicU64::new(0),
+        stop: AtomicBool::new(false),
+    });
+
+    let started = Instant::now();
+    let deadline = started + options.duration;
+    let workers: Vec<_> = (0..options.workers)
+        .map(|i| {
+            let rng = Rng::new(options.seed.wrapping_add(i as u64));
+            tokio::spawn(soak.clone().worker(rng, deadline))
+        })
+        .collect();
+
+    let mut rng = Rng::new(options.seed ^ u64::MAX);
+    let mut failure = None;
+    while Instant::now() < deadline && !soak.stop.load(Ordering::Relaxed) {
+        tokio::time::sleep(options.check_interval.min(deadline - Instant::now())).await;
+        match soak.check(&mut rng).await {
+            Ok(records) => println!(
+                "soak: elapsed={}s requests={} runs={} audit_records={records}",
+                started.elapsed().as_secs(),
+                soak.requests.load(Ordering::Relaxed),
+                soak.known.lock().unwrap().run_ids.len()
+            ),
+            Err(err) => {
+                soak.stop.store(true, Ordering::Relaxed);
+                failure = Some(err);
+            }
+        }
+    }
+    for worker in workers {
+        match worker.await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => failure = failure.or(Some(err)),
+            Err(err) => failure = failure.or(Some(format!("worker panicked: {err}"))),
+        }
+    }
+    if let Some(err) = failure {
+        return Err(err);
+    }
+
+    soak.check(&mut rng).await?;
+    soak.final_check().await?;
+    arbiter_server::verify_audit_chain(&audit_path)?;
+    let _ = std::fs::remove_dir_all(dir);
+    println!(
+        "soak: passed after {} requests",
+        soak.requests.load(Ordering::Relaxed)
+    );
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    if let Err(err) = run(options).await {
+        eprintln!("soak failed: {err}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> impl Iterator<Item = String> {
+        list.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn parse_soak_options() {
+        let options = parse_args(args(&["--duration", "2h", "--store", "sqlite"])).unwrap();
+        assert_eq!(options.duration, Duration::from_secs(7200));
+        assert_eq!(options.store, "sqlite");
+        assert_eq!(options.workers, 8);
+        assert!(parse_args(args(&["--workers", "0"])).is_err());
+        assert!(parse_args(args(&["--store", "postgres"])).is_err());
+        assert!(parse_args(args(&["--duration"])).is_err());
+    }
+}
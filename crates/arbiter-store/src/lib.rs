@@ -0,0 +1,251 @@
+mod memory;
+#[cfg(feature = "sqlite")]
+mod migrations;
+mod snapshot;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use arbiter_contracts::{ArchivedRun, LookupKind, RunEnvelope, StoreStats};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use memory::MemoryStore;
+pub use snapshot::{export_snapshot, import_snapshot, SNAPSHOT_FORMAT_VERSION};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdempotencyRecord {
+    pub payload_hash: String,
+    pub response_json: String,
+    pub created_at: String,
+    /// Canonical request body, kept only with `store.retain_request_payloads`.
+    #[serde(default)]
+    pub payload_json: Option<String>,
+}
+
+/// A record in a run that carries a looked-up id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdMatch {
+    pub kind: LookupKind,
+    pub run_id: String,
+    pub archived: bool,
+}
+
+/// Every id a run's records carry, with the kind of record. A step matches
+/// by `step_id` or `client_step_id`.
+fn run_record_ids(run: &RunEnvelope) -> Vec<(LookupKind, &str)> {
+    let mut ids = vec![
+        (LookupKind::Run, run.run.run_id.as_str()),
+        (LookupKind::OperationRequest, run.run.request_id.as_str()),
+    ];
+    for step in &run.steps {
+        ids.push((LookupKind::Step, step.step_id.as_str()));
+        if let Some(client_step_id) = step.intent.client_step_id.as_deref() {
+            if client_step_id != step.step_id {
+                ids.push((LookupKind::Step, client_step_id));
+            }
+        }
+        ids.push((LookupKind::Decision, step.decision.decision_id.as_str()));
+        if let Some(permit) = &step.permit {
+            ids.push((LookupKind::Permit, permit.permit_id.as_str()));
+        }
+    }
+    for approval in &run.approvals {
+        ids.push((LookupKind::Approval, approval.approval_id.as_str()));
+    }
+    ids
+}
+
+fn matches_in(run: &RunEnvelope, id: &str, archived: bool, matches: &mut Vec<IdMatch>) {
+    for (kind, record_id) in run_record_ids(run) {
+        if record_id == id {
+            matches.push(IdMatch {
+                kind,
+                run_id: run.run.run_id.clone(),
+                archived,
+            });
+        }
+    }
+}
+
+/// `Conflict` and `Unsupported` surface as `409 conflict`, `Backend` as
+/// `500 internal_error`.
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    Unsupported(String),
+    #[error("{0}")]
+    Backend(String),
+}
+
+/// Storage backend behind `arbiter-server`. Embedders can supply their own
+/// through `arbiter_server::build_app_with_store`. The server calls it on a
+/// blocking thread with exclusive access, so implementations may block and
+/// need no locking of their own, and it keeps the audit log itself.
+/// Timestamps are RFC 3339 strings in UTC passed in by the caller; a store
+/// never reads the clock.
+pub trait Store: Send {
+    /// Short backend name reported by the startup report, e.g. `sqlite`.
+    fn kind(&self) -> &str;
+
+    fn get_run(&self, run_id: &str) -> Result<Option<RunEnvelope>, StoreError>;
+
+    /// Inserts or replaces the run keyed by `run.run.run_id`. Returns the
+    /// ids of any archived runs evicted to stay within a capacity limit, so
+    /// the caller can audit the lost history.
+    fn put_run(&mut self, run: RunEnvelope) -> Result<Vec<String>, StoreError>;
+
+    /// Looks up a run by `request_id` in both the hot and archived runs.
+    fn find_run_by_request_id(&self, request_id: &str) -> Result<Option<RunEnvelope>, StoreError>;
+
+    fn get_archived_run(&self, run_id: &str) -> Result<Option<RunEnvelope>, StoreError>;
+
+    /// Lists the hot (non-archived) runs.
+    fn list_runs(&self) -> Result<Vec<RunEnvelope>, StoreError>;
+
+    /// Moves up to `limit` terminal runs last updated before `threshold`
+    /// into the archive, stamped with `archived_at`. Returns the number of
+    /// runs moved.
+    fn archive_terminal_runs(
+        &mut self,
+        threshold: DateTime<Utc>,
+        archived_at: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<usize, StoreError>;
+
+    fn map_approval_to_run(&mut self, approval_id: &str, run_id: &str) -> Result<(), StoreError>;
+
+    fn run_id_for_approval(&self, approval_id: &str) -> Result<Option<String>, StoreError>;
+
+    fn remove_approval_mapping(&mut self, approval_id: &str) -> Result<(), StoreError>;
+
+    fn list_approval_mappings(&self) -> Result<Vec<(String, String)>, StoreError>;
+
+    fn list_archived_runs(&self) -> Result<Vec<ArchivedRun>, StoreError>;
+
+    /// Inserts or replaces an archived run, keeping its `archived_at`. Used
+    /// to import snapshots.
+    fn put_archived_run(&mut self, archived: ArchivedRun) -> Result<(), StoreError>;
+
+    /// Returns the record for `key` whether or not it has expired; the
+    /// server applies the retention window.
+    fn get_idempotency(&self, key: &str) -> Result<Option<IdempotencyRecord>, StoreError>;
+
+    /// Lists every record, expired or not, without counting as a lookup for
+    /// any recency-based eviction.
+    fn list_idempotency(&self) -> Result<Vec<(String, IdempotencyRecord)>, StoreError>;
+
+    /// Stores `record` under `key`. An existing record is only replaced when
+    /// it was created before `expired_before`. Returns the keys of any other
+    /// records evicted to stay within a capacity limit, so the caller can
+    /// audit the lost replay protection.
+    fn put_idempotency(
+        &mut self,
+        key: &str,
+        record: IdempotencyRecord,
+        expired_before: DateTime<Utc>,
+    ) -> Result<Vec<String>, StoreError>;
+
+    /// Deletes up to `limit` records created before `expired_before`.
+    /// Returns the number deleted.
+    fn prune_idempotency(
+        &mut self,
+        expired_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<usize, StoreError>;
+
+    /// Schema version found in the backing database, for backends that have one.
+    fn schema_version(&self) -> Option<i64> {
+        None
+    }
+
+    /// Schema version this build of the backend expects.
+    fn supported_schema_version(&self) -> Option<i64> {
+        None
+    }
+
+    /// Writes a consistent copy of the store to `out_path`.
+    fn backup(&self, _out_path: &str) -> Result<(), StoreError> {
+        Err(StoreError::Unsupported(format!(
+            "store backup is not supported by store.kind={}",
+            self.kind()
+        )))
+    }
+
+    /// Cheap round trip to the backend for readiness checks. Backends with
+    /// nothing to reach are always ready.
+    fn ping(&self) -> Result<(), StoreError> {
+        Ok(())
+    }
+
+    /// Returns up to `max_pages` free pages to the filesystem and reports
+    /// how many were released.
+    fn vacuum(&mut self, _max_pages: usize) -> Result<usize, StoreError> {
+        Err(StoreError::Unsupported(format!(
+            "vacuum is not supported by store.kind={}",
+            self.kind()
+        )))
+    }
+
+    /// Refreshes the query planner's statistics.
+    fn analyze(&mut self) -> Result<(), StoreError> {
+        Err(StoreError::Unsupported(format!(
+            "analyze is not supported by store.kind={}",
+            self.kind()
+        )))
+    }
+
+    /// Starts a batch of writes that `commit_batch` keeps and
+    /// `rollback_batch` undoes. Batches do not nest.
+    fn begin_batch(&mut self) -> Result<(), StoreError> {
+        Err(StoreError::Unsupported(format!(
+            "write batches are not supported by store.kind={}",
+            self.kind()
+        )))
+    }
+
+    fn commit_batch(&mut self) -> Result<(), StoreError> {
+        Err(StoreError::Unsupported(format!(
+            "write batches are not supported by store.kind={}",
+            self.kind()
+        )))
+    }
+
+    fn rollback_batch(&mut self) -> Result<(), StoreError> {
+        Err(StoreError::Unsupported(format!(
+            "write batches are not supported by store.kind={}",
+            self.kind()
+        )))
+    }
+
+    /// Records in hot or archived runs that carry `id`, for
+    /// `GET /v1/lookup/{id}`. The default reads every run; backends keep an
+    /// index instead where reading runs is expensive.
+    fn lookup_id(&self, id: &str) -> Result<Vec<IdMatch>, StoreError> {
+        let mut matches = Vec::new();
+        for run in self.list_runs()? {
+            matches_in(&run, id, false, &mut matches);
+        }
+        for archived in self.list_archived_runs()? {
+            matches_in(&archived.envelope, id, true, &mut matches);
+        }
+        Ok(matches)
+    }
+
+    /// Row counts for `GET /v1/admin/store/stats`.
+    fn stats(&self) -> Result<StoreStats, StoreError>;
+
+    /// Diagnostic `key=value` lines for `arbiter store-doctor`.
+    fn doctor(&self) -> Result<Vec<String>, StoreError>;
+}
+
+pub fn is_idempotency_expired(created_at: &str, expired_before: DateTime<Utc>) -> bool {
+    let Ok(ts) = DateTime::parse_from_rfc3339(created_at) else {
+        return false;
+    };
+    ts.with_timezone(&Utc) < expired_before
+}
@@ -0,0 +1,613 @@
+use arbiter_contracts::{ArchivedRun, RunEnvelope, StoreStats};
+use arbiter_kernel::state_machine::is_terminal_run;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+use crate::{is_idempotency_expired, matches_in, IdMatch, IdempotencyRecord, Store, StoreError};
+
+/// In-process store. Everything is lost on restart except idempotency
+/// records when a journal path is set.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    runs: HashMap<String, RunEnvelope>,
+    archived_runs: HashMap<String, ArchivedRun>,
+    approvals: HashMap<String, String>,
+    idempotency: HashMap<String, IdempotencyRecord>,
+    idempotency_journal_path: Option<String>,
+    max_idempotency_entries: Option<usize>,
+    max_runs: Option<usize>,
+    // Lookups refresh recency through `&self`, hence the `RefCell`.
+    idempotency_recency: RefCell<Recency>,
+    // State to restore if the open write batch is rolled back.
+    batch: Option<Box<MemoryBatch>>,
+}
+
+#[derive(Debug)]
+struct MemoryBatch {
+    runs: HashMap<String, RunEnvelope>,
+    archived_runs: HashMap<String, ArchivedRun>,
+    approvals: HashMap<String, String>,
+    idempotency: HashMap<String, IdempotencyRecord>,
+    idempotency_recency: Recency,
+}
+
+/// Least-recently-used order of idempotency keys.
+#[derive(Debug, Default, Clone)]
+struct Recency {
+    next_tick: u64,
+    tick_by_key: HashMap<String, u64>,
+    key_by_tick: BTreeMap<u64, String>,
+}
+
+impl Recency {
+    fn touch(&mut self, key: &str) {
+        self.remove(key);
+        self.next_tick += 1;
+        self.tick_by_key.insert(key.to_string(), self.next_tick);
+        self.key_by_tick.insert(self.next_tick, key.to_string());
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(tick) = self.tick_by_key.remove(key) {
+            self.key_by_tick.remove(&tick);
+        }
+    }
+
+    fn pop_oldest(&mut self) -> Option<String> {
+        let (_, key) = self.key_by_tick.pop_first()?;
+        self.tick_by_key.remove(&key);
+        Some(key)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IdempotencyJournalEntry {
+    key: String,
+    #[serde(flatten)]
+    record: IdempotencyRecord,
+}
+
+impl MemoryStore {
+    /// With `idempotency_journal_path`, every idempotency record is appended
+    /// and fsynced to that JSONL file, and records created at or after
+    /// `expired_before` are replayed from it now. With
+    /// `max_idempotency_entries`, storing a record beyond the cap evicts the
+    /// least recently stored or replayed one; a replayed journal keeps its
+    /// newest records. With `max_runs`, storing a new run beyond the cap
+    /// evicts the longest-archived runs, and fails when too few are archived.
+    pub fn new(
+        idempotency_journal_path: Option<&str>,
+        expired_before: DateTime<Utc>,
+        max_idempotency_entries: Option<usize>,
+        max_runs: Option<usize>,
+    ) -> Result<Self, String> {
+        let idempotency = match idempotency_journal_path {
+            Some(path) => load_idempotency_journal(path, expired_before, max_idempotency_entries)?,
+            None => HashMap::new(),
+        };
+        let mut by_age: Vec<(&String, &IdempotencyRecord)> = idempotency.iter().collect();
+        by_age.sort_by(|a, b| a.1.created_at.cmp(&b.1.created_at));
+        let mut recency = Recency::default();
+        for (key, _) in by_age {
+            recency.touch(key);
+        }
+        Ok(Self {
+            idempotency,
+            idempotency_journal_path: idempotency_journal_path.map(str::to_string),
+            max_idempotency_entries,
+            max_runs,
+            idempotency_recency: RefCell::new(recency),
+            ..Self::default()
+        })
+    }
+
+    fn evict_idempotency_over_cap(&mut self) -> Vec<String> {
+        let Some(max) = self.max_idempotency_entries else {
+            return Vec::new();
+        };
+        let recency = self.idempotency_recency.get_mut();
+        let mut evicted = Vec::new();
+        while self.idempotency.len() > max {
+            let Some(key) = recency.pop_oldest() else {
+                break;
+            };
+            self.idempotency.remove(&key);
+            evicted.push(key);
+        }
+        evicted
+    }
+
+    /// Evicts the longest-archived runs, and the approval mappings naming
+    /// them, so one more run fits within `max_runs`. Hot runs are never
+    /// evicted: with too few archived runs the new run is rejected instead.
+    fn evict_archived_runs_for_new_run(&mut self) -> Result<Vec<String>, StoreError> {
+        let Some(max) = self.max_runs else {
+            return Ok(Vec::new());
+        };
+        let excess = (self.runs.len() + self.archived_runs.len() + 1).saturating_sub(max);
+        if excess == 0 {
+            return Ok(Vec::new());
+        }
+        if excess > self.archived_runs.len() {
+            return Err(StoreError::Conflict(format!(
+                "store.memory.max_runs={max} reached with {} hot runs; only archived runs are evicted",
+                self.runs.len()
+            )));
+        }
+        let mut by_age: Vec<(&String, &String)> = self
+            .archived_runs
+            .iter()
+            .map(|(run_id, archived)| (&archived.archived_at, run_id))
+            .collect();
+        by_age.sort();
+        let evicted: Vec<String> = by_age
+            .into_iter()
+            .take(excess)
+            .map(|(_, run_id)| run_id.clone())
+            .collect();
+        for run_id in &evicted {
+            self.archived_runs.remove(run_id);
+        }
+        self.approvals.retain(|_, run_id| !evicted.contains(run_id));
+        Ok(evicted)
+    }
+}
+
+/// Replays the memory-mode idempotency journal and rewrites it without
+/// expired entries, or the oldest ones beyond `max_entries`, so the file only
+/// grows within one retention window.
+fn load_idempotency_journal(
+    path: &str,
+    expired_before: DateTime<Utc>,
+    max_entries: Option<usize>,
+) -> Result<HashMap<String, IdempotencyRecord>, String> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(format!("failed to read idempotency journal {path}: {err}")),
+    };
+    let mut records = HashMap::new();
+    for (idx, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: IdempotencyJournalEntry = serde_json::from_str(line).map_err(|err| {
+            format!(
+                "invalid idempotency journal entry at line {}: {err}",
+                idx + 1
+            )
+        })?;
+        if !is_idempotency_expired(&entry.record.created_at, expired_before) {
+            records.entry(entry.key).or_insert(entry.record);
+        }
+    }
+    if let Some(max) = max_entries.filter(|max| records.len() > *max) {
+        let mut newest: Vec<(String, IdempotencyRecord)> = records.into_iter().collect();
+        newest.sort_by(|a, b| b.1.created_at.cmp(&a.1.created_at));
+        newest.truncate(max);
+        records = newest.into_iter().collect();
+    }
+
+    write_idempotency_journal(path, &records)?;
+    Ok(records)
+}
+
+/// Replaces the journal with exactly `records`.
+fn write_idempotency_journal(
+    path: &str,
+    records: &HashMap<String, IdempotencyRecord>,
+) -> Result<(), String> {
+    let mut compacted = String::new();
+    for (key, record) in records {
+        let line = serde_json::to_string(&IdempotencyJournalEntry {
+            key: key.clone(),
+            record: record.clone(),
+        })
+        .map_err(|err| format!("failed to encode idempotency journal entry: {err}"))?;
+        compacted.push_str(&line);
+        compacted.push('\n');
+    }
+    std::fs::write(path, compacted)
+        .map_err(|err| format!("failed to compact idempotency journal {path}: {err}"))
+}
+
+fn append_idempotency_journal(
+    path: &str,
+    key: &str,
+    record: &IdempotencyRecord,
+) -> Result<(), StoreError> {
+    let mut line = serde_json::to_string(&IdempotencyJournalEntry {
+        key: key.to_string(),
+        record: record.clone(),
+    })
+    .map_err(|err| StoreError::Backend(err.to_string()))?;
+    line.push('\n');
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| StoreError::Backend(format!("failed to open idempotency journal: {err}")))?;
+    file.write_all(line.as_bytes()).map_err(|err| {
+        StoreError::Backend(format!("failed to write idempotency journal: {err}"))
+    })?;
+    file.sync_data()
+        .map_err(|err| StoreError::Backend(format!("failed to sync idempotency journal: {err}")))
+}
+
+impl Store for MemoryStore {
+    fn kind(&self) -> &str {
+        "memory"
+    }
+
+    fn get_run(&self, run_id: &str) -> Result<Option<RunEnvelope>, StoreError> {
+        Ok(self.runs.get(run_id).cloned())
+    }
+
+    fn put_run(&mut self, run: RunEnvelope) -> Result<Vec<String>, StoreError> {
+        let evicted = if self.runs.contains_key(&run.run.run_id) {
+            Vec::new()
+        } else {
+            self.evict_archived_runs_for_new_run()?
+        };
+        self.runs.insert(run.run.run_id.clone(), run);
+        Ok(evicted)
+    }
+
+    fn find_run_by_request_id(&self, request_id: &str) -> Result<Option<RunEnvelope>, StoreError> {
+        Ok(self
+            .runs
+            .values()
+            .chain(self.archived_runs.values().map(|a| &a.envelope))
+            .find(|r| r.run.request_id == request_id)
+            .cloned())
+    }
+
+    fn get_archived_run(&self, run_id: &str) -> Result<Option<RunEnvelope>, StoreError> {
+        Ok(self
+            .archived_runs
+            .get(run_id)
+            .map(|archived| archived.envelope.clone()))
+    }
+
+    fn list_runs(&self) -> Result<Vec<RunEnvelope>, StoreError> {
+        Ok(self.runs.values().cloned().collect())
+    }
+
+    fn archive_terminal_runs(
+        &mut self,
+        threshold: DateTime<Utc>,
+        archived_at: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<usize, StoreError> {
+        let run_ids: Vec<String> = self
+            .runs
+            .values()
+            .filter(|r| is_terminal_run(&r.run.status))
+            .filter(|r| {
+                DateTime::parse_from_rfc3339(&r.run.updated_at)
+                    .map(|ts| ts.with_timezone(&Utc) < threshold)
+                    .unwrap_or(false)
+            })
+            .map(|r| r.run.run_id.clone())
+            .take(limit)
+            .collect();
+        for run_id in &run_ids {
+            if let Some(envelope) = self.runs.remove(run_id) {
+                self.archived_runs.insert(
+                    run_id.clone(),
+                    ArchivedRun {
+                        archived_at: archived_at.to_rfc3339(),
+                        envelope,
+                    },
+                );
+            }
+        }
+        Ok(run_ids.len())
+    }
+
+    fn map_approval_to_run(&mut self, approval_id: &str, run_id: &str) -> Result<(), StoreError> {
+        self.approvals
+            .insert(approval_id.to_string(), run_id.to_string());
+        Ok(())
+    }
+
+    fn run_id_for_approval(&self, approval_id: &str) -> Result<Option<String>, StoreError> {
+        Ok(self.approvals.get(approval_id).cloned())
+    }
+
+    fn remove_approval_mapping(&mut self, approval_id: &str) -> Result<(), StoreError> {
+        self.approvals.remove(approval_id);
+        Ok(())
+    }
+
+    fn list_approval_mappings(&self) -> Result<Vec<(String, String)>, StoreError> {
+        Ok(self
+            .approvals
+            .iter()
+            .map(|(approval_id, run_id)| (approval_id.clone(), run_id.clone()))
+            .collect())
+    }
+
+    fn list_archived_runs(&self) -> Result<Vec<ArchivedRun>, StoreError> {
+        Ok(self.archived_runs.values().cloned().collect())
+    }
+
+    fn put_archived_run(&mut self, archived: ArchivedRun) -> Result<(), StoreError> {
+        self.archived_runs
+            .insert(archived.envelope.run.run_id.clone(), archived);
+        Ok(())
+    }
+
+    fn get_idempotency(&self, key: &str) -> Result<Option<IdempotencyRecord>, StoreError> {
+        let record = self.idempotency.get(key).cloned();
+        if record.is_some() {
+            self.idempotency_recency.borrow_mut().touch(key);
+        }
+        Ok(record)
+    }
+
+    fn list_idempotency(&self) -> Result<Vec<(String, IdempotencyRecord)>, StoreError> {
+        Ok(self
+            .idempotency
+            .iter()
+            .map(|(key, record)| (key.clone(), record.clone()))
+            .collect())
+    }
+
+    fn put_idempotency(
+        &mut self,
+        key: &str,
+        record: IdempotencyRecord,
+        expired_before: DateTime<Utc>,
+    ) -> Result<Vec<String>, StoreError> {
+        if let Some(existing) = self.idempotency.get(key) {
+            if !is_idempotency_expired(&existing.created_at, expired_before) {
+                return Ok(Vec::new());
+            }
+        }
+        if let Some(path) = self.idempotency_journal_path.as_deref() {
+            append_idempotency_journal(path, key, &record)?;
+        }
+        self.idempotency.insert(key.to_string(), record);
+        self.idempotency_recency.get_mut().touch(key);
+        Ok(self.evict_idempotency_over_cap())
+    }
+
+    fn prune_idempotency(
+        &mut self,
+        expired_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<usize, StoreError> {
+        let expired: Vec<String> = self
+            .idempotency
+            .iter()
+            .filter(|(_, record)| is_idempotency_expired(&record.created_at, expired_before))
+            .map(|(key, _)| key.clone())
+            .take(limit)
+            .collect();
+        let recency = self.idempotency_recency.get_mut();
+        for key in &expired {
+            self.idempotency.remove(key);
+            recency.remove(key);
+        }
+        Ok(expired.len())
+    }
+
+    fn backup(&self, _out_path: &str) -> Result<(), StoreError> {
+        Err(StoreError::Unsupported(
+            "store backup requires store.kind=sqlite".to_string(),
+        ))
+    }
+
+    fn lookup_id(&self, id: &str) -> Result<Vec<IdMatch>, StoreError> {
+        let mut matches = Vec::new();
+        for run in self.runs.values() {
+            matches_in(run, id, false, &mut matches);
+        }
+        for archived in self.archived_runs.values() {
+            matches_in(&archived.envelope, id, true, &mut matches);
+        }
+        Ok(matches)
+    }
+
+    fn begin_batch(&mut self) -> Result<(), StoreError> {
+        if self.batch.is_some() {
+            return Err(StoreError::Backend(
+                "a write batch is already open".to_string(),
+            ));
+        }
+        self.batch = Some(Box::new(MemoryBatch {
+            runs: self.runs.clone(),
+            archived_runs: self.archived_runs.clone(),
+            approvals: self.approvals.clone(),
+            idempotency: self.idempotency.clone(),
+            idempotency_recency: self.idempotency_recency.get_mut().clone(),
+        }));
+        Ok(())
+    }
+
+    fn commit_batch(&mut self) -> Result<(), StoreError> {
+        self.batch = None;
+        Ok(())
+    }
+
+    /// Restores the state from `begin_batch`. The journal is rewritten
+    /// because idempotency records written in the batch were appended to it.
+    fn rollback_batch(&mut self) -> Result<(), StoreError> {
+        let Some(batch) = self.batch.take() else {
+            return Err(StoreError::Backend("no write batch is open".to_string()));
+        };
+        self.runs = batch.runs;
+        self.archived_runs = batch.archived_runs;
+        self.approvals = batch.approvals;
+        self.idempotency = batch.idempotency;
+        *self.idempotency_recency.get_mut() = batch.idempotency_recency;
+        match self.idempotency_journal_path.as_deref() {
+            Some(path) => {
+                write_idempotency_journal(path, &self.idempotency).map_err(StoreError::Backend)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn stats(&self) -> Result<StoreStats, StoreError> {
+        let mut runs_by_status = BTreeMap::new();
+        for run in self.runs.values() {
+            let status = serde_json::to_value(&run.run.status)
+                .map_err(|err| StoreError::Backend(err.to_string()))?;
+            *runs_by_status
+                .entry(status.as_str().unwrap_or_default().to_string())
+                .or_insert(0) += 1;
+        }
+        Ok(StoreStats {
+            kind: self.kind().to_string(),
+            runs: self.runs.len() as u64,
+            archived_runs: self.archived_runs.len() as u64,
+            approval_mappings: self.approvals.len() as u64,
+            idempotency_records: self.idempotency.len() as u64,
+            runs_by_status,
+        })
+    }
+
+    fn doctor(&self) -> Result<Vec<String>, StoreError> {
+        Ok(vec![
+            "store=memory".to_string(),
+            format!("runs={}", self.runs.len()),
+            format!("archived_runs={}", self.archived_runs.len()),
+            format!("idempotency_records={}", self.idempotency.len()),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arbiter_contracts::{Run, RunStatus};
+    use chrono::Duration;
+
+    fn envelope(run_id: &str) -> RunEnvelope {
+        RunEnvelope {
+            run: Run {
+                run_id: run_id.to_string(),
+                request_id: format!("req_{run_id}"),
+                requester: "alice".to_string(),
+                source: "cli".to_string(),
+                objective: "deploy".to_string(),
+                environment: "dev".to_string(),
+                status: RunStatus::Accepted,
+                created_at: "2026-01-01T00:00:00Z".to_string(),
+                updated_at: "2026-01-01T00:00:00Z".to_string(),
+                risk_summary: serde_json::Value::Null,
+            },
+            steps: Vec::new(),
+            approvals: Vec::new(),
+            permits: Vec::new(),
+        }
+    }
+
+    fn record(created_at: DateTime<Utc>, payload_hash: &str) -> IdempotencyRecord {
+        IdempotencyRecord {
+            payload_hash: payload_hash.to_string(),
+            response_json: "{}".to_string(),
+            created_at: created_at.to_rfc3339(),
+            payload_json: None,
+        }
+    }
+
+    #[test]
+    fn put_idempotency_only_replaces_expired_records() {
+        let now = Utc::now();
+        let expired_before = now - Duration::hours(1);
+        let mut store = MemoryStore::new(None, expired_before, None, None).unwrap();
+
+        store
+            .put_idempotency("k", record(now, "first"), expired_before)
+            .unwrap();
+        store
+            .put_idempotency("k", record(now, "second"), expired_before)
+            .unwrap();
+        assert_eq!(
+            store.get_idempotency("k").unwrap().unwrap().payload_hash,
+            "first"
+        );
+
+        store
+            .put_idempotency(
+                "old",
+                record(now - Duration::hours(2), "stale"),
+                expired_before,
+            )
+            .unwrap();
+        store
+            .put_idempotency("old", record(now, "fresh"), expired_before)
+            .unwrap();
+        assert_eq!(
+            store.get_idempotency("old").unwrap().unwrap().payload_hash,
+            "fresh"
+        );
+
+        assert_eq!(
+            store
+                .prune_idempotency(now + Duration::hours(1), 10)
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn idempotency_cap_evicts_least_recently_used() {
+        let now = Utc::now();
+        let expired_before = now - Duration::hours(1);
+        let mut store = MemoryStore::new(None, expired_before, Some(2), None).unwrap();
+
+        assert!(store
+            .put_idempotency("a", record(now, "a"), expired_before)
+            .unwrap()
+            .is_empty());
+        store
+            .put_idempotency("b", record(now, "b"), expired_before)
+            .unwrap();
+        store.get_idempotency("a").unwrap();
+        let evicted = store
+            .put_idempotency("c", record(now, "c"), expired_before)
+            .unwrap();
+        assert_eq!(evicted, vec!["b".to_string()]);
+        assert!(store.get_idempotency("a").unwrap().is_some());
+        assert!(store.get_idempotency("b").unwrap().is_none());
+    }
+
+    #[test]
+    fn run_cap_evicts_the_longest_archived_run() {
+        let mut store = MemoryStore::new(None, Utc::now(), None, Some(2)).unwrap();
+        for (run_id, archived_at) in [
+            ("old", "2026-01-01T00:00:00Z"),
+            ("new", "2026-02-01T00:00:00Z"),
+        ] {
+            store
+                .put_archived_run(ArchivedRun {
+                    archived_at: archived_at.to_string(),
+                    envelope: envelope(run_id),
+                })
+                .unwrap();
+        }
+        store.map_approval_to_run("apr_old", "old").unwrap();
+
+        assert_eq!(
+            store.put_run(envelope("hot")).unwrap(),
+            vec!["old".to_string()]
+        );
+        assert!(store.put_run(envelope("hot")).unwrap().is_empty());
+        assert!(store.run_id_for_approval("apr_old").unwrap().is_none());
+        assert_eq!(
+            store.put_run(envelope("hot2")).unwrap(),
+            vec!["new".to_string()]
+        );
+        assert!(matches!(
+            store.put_run(envelope("hot3")),
+            Err(StoreError::Conflict(_))
+        ));
+    }
+}
@@ -0,0 +1,106 @@
+use rusqlite::Connection;
+
+/// Forward-only SQLite schema steps. Step `i` upgrades a database from
+/// version `i` to `i + 1`, tracked in `PRAGMA user_version`. Shipped steps
+/// must never be edited or reordered; schema changes go in a new step.
+///
+/// Step 1 uses `IF NOT EXISTS` because databases created before versioning
+/// already have these tables at version 0.
+const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE IF NOT EXISTS runs (
+        run_id TEXT PRIMARY KEY,
+        request_id TEXT UNIQUE NOT NULL,
+        envelope_json TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS runs_archive (
+        run_id TEXT PRIMARY KEY,
+        request_id TEXT UNIQUE NOT NULL,
+        envelope_json TEXT NOT NULL,
+        archived_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS approvals (
+        approval_id TEXT PRIMARY KEY,
+        run_id TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS idempotency (
+        idem_key TEXT PRIMARY KEY,
+        payload_hash TEXT NOT NULL,
+        response_json TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    );
+",
+    "
+    CREATE INDEX idempotency_created_at ON idempotency (created_at);
+",
+    "
+    ALTER TABLE idempotency ADD COLUMN payload_json TEXT;
+",
+    "
+    CREATE TABLE lookup_ids (
+        id TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        run_id TEXT NOT NULL,
+        PRIMARY KEY (id, kind, run_id)
+    ) WITHOUT ROWID;
+    WITH src AS (
+        SELECT run_id, envelope_json FROM runs
+        UNION ALL SELECT run_id, envelope_json FROM runs_archive
+    )
+    INSERT OR IGNORE INTO lookup_ids (id, kind, run_id)
+    SELECT id, kind, run_id FROM (
+        SELECT run_id AS id, 'run' AS kind, run_id FROM src
+        UNION ALL SELECT json_extract(envelope_json, '$.run.request_id'), 'operation_request', run_id FROM src
+        UNION ALL SELECT json_extract(s.value, '$.step_id'), 'step', run_id
+            FROM src, json_each(envelope_json, '$.steps') AS s
+        UNION ALL SELECT json_extract(s.value, '$.intent.client_step_id'), 'step', run_id
+            FROM src, json_each(envelope_json, '$.steps') AS s
+        UNION ALL SELECT json_extract(s.value, '$.decision.decision_id'), 'decision', run_id
+            FROM src, json_each(envelope_json, '$.steps') AS s
+        UNION ALL SELECT json_extract(s.value, '$.permit.permit_id'), 'permit', run_id
+            FROM src, json_each(envelope_json, '$.steps') AS s
+        UNION ALL SELECT json_extract(a.value, '$.approval_id'), 'approval', run_id
+            FROM src, json_each(envelope_json, '$.approvals') AS a
+    ) WHERE id IS NOT NULL;
+",
+];
+
+pub(crate) const SQLITE_SCHEMA_VERSION: i64 = MIGRATIONS.len() as i64;
+
+/// Reads the schema version of a database another process may be
+/// migrating, without applying any step. A version older than this binary
+/// fails, since the missing steps are left to the process that owns it.
+pub(crate) fn current_version(conn: &Connection) -> Result<i64, String> {
+    let version: i64 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|err| format!("failed to read sqlite schema version: {err}"))?;
+    if version < SQLITE_SCHEMA_VERSION {
+        return Err(format!(
+            "sqlite schema version {version} is older than {SQLITE_SCHEMA_VERSION}; it is migrated when the server holding the store restarts"
+        ));
+    }
+    Ok(version)
+}
+
+/// Applies every pending step, each in its own transaction together with the
+/// version bump, and returns the resulting version. A database newer than
+/// this binary is left untouched; the startup check reports the mismatch.
+pub(crate) fn migrate(conn: &mut Connection) -> Result<i64, String> {
+    let mut version: i64 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|err| format!("failed to read sqlite schema version: {err}"))?;
+    while version < SQLITE_SCHEMA_VERSION {
+        let next = version + 1;
+        let tx = conn
+            .transaction()
+            .map_err(|err| format!("failed to start sqlite migration {next}: {err}"))?;
+        tx.execute_batch(MIGRATIONS[version as usize])
+            .map_err(|err| format!("sqlite migration {next} failed: {err}"))?;
+        tx.pragma_update(None, "user_version", next)
+            .map_err(|err| format!("failed to stamp sqlite schema version {next}: {err}"))?;
+        tx.commit()
+            .map_err(|err| format!("failed to commit sqlite migration {next}: {err}"))?;
+        version = next;
+    }
+    Ok(version)
+}
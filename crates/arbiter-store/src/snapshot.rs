@@ -0,0 +1,156 @@
+use arbiter_contracts::{ApprovalMapping, IdempotencySnapshotRecord, StoreSnapshot};
+use chrono::{DateTime, Utc};
+
+use crate::{IdempotencyRecord, Store, StoreError};
+
+/// Bumped whenever `StoreSnapshot` changes shape; import rejects others.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// Copies everything `store` holds, expired idempotency records included,
+/// sorted so the same state always produces the same snapshot.
+pub fn export_snapshot(store: &dyn Store) -> Result<StoreSnapshot, StoreError> {
+    let mut runs = store.list_runs()?;
+    runs.sort_by(|a, b| a.run.run_id.cmp(&b.run.run_id));
+    let mut archived_runs = store.list_archived_runs()?;
+    archived_runs.sort_by(|a, b| a.envelope.run.run_id.cmp(&b.envelope.run.run_id));
+    let mut approval_mappings: Vec<ApprovalMapping> = store
+        .list_approval_mappings()?
+        .into_iter()
+        .map(|(approval_id, run_id)| ApprovalMapping {
+            approval_id,
+            run_id,
+        })
+        .collect();
+    approval_mappings.sort_by(|a, b| a.approval_id.cmp(&b.approval_id));
+    let mut idempotency_records: Vec<IdempotencySnapshotRecord> = store
+        .list_idempotency()?
+        .into_iter()
+        .map(|(key, record)| IdempotencySnapshotRecord {
+            key,
+            payload_hash: record.payload_hash,
+            response_json: record.response_json,
+            created_at: record.created_at,
+            payload_json: record.payload_json,
+        })
+        .collect();
+    idempotency_records.sort_by(|a, b| a.key.cmp(&b.key));
+
+    Ok(StoreSnapshot {
+        format_version: SNAPSHOT_FORMAT_VERSION,
+        source_kind: store.kind().to_string(),
+        runs,
+        archived_runs,
+        approval_mappings,
+        idempotency_records,
+    })
+}
+
+/// Loads `snapshot` into `store`, which must be empty: merging two stores'
+/// runs and idempotency keys has no safe general answer. Idempotency
+/// records are written as-is; the importing server applies its own
+/// retention window to them. Returns the evicted keys if `store` has an
+/// idempotency cap smaller than the snapshot. The rows are written in one
+/// batch, so a failed import leaves the store empty and can be retried.
+pub fn import_snapshot(
+    store: &mut dyn Store,
+    snapshot: StoreSnapshot,
+) -> Result<Vec<String>, StoreError> {
+    if snapshot.format_version != SNAPSHOT_FORMAT_VERSION {
+        return Err(StoreError::Unsupported(format!(
+            "snapshot format_version {} is not supported (expected {SNAPSHOT_FORMAT_VERSION})",
+            snapshot.format_version
+        )));
+    }
+    let stats = store.stats()?;
+    if stats.runs + stats.archived_runs + stats.approval_mappings + stats.idempotency_records > 0 {
+        return Err(StoreError::Conflict(format!(
+            "store.kind={} is not empty; snapshots can only be imported into an empty store",
+            store.kind()
+        )));
+    }
+
+    store.begin_batch()?;
+    match load_rows(store, snapshot) {
+        Ok(evicted) => {
+            store.commit_batch()?;
+            Ok(evicted)
+        }
+        Err(err) => match store.rollback_batch() {
+            Ok(()) => Err(err),
+            Err(rollback) => Err(StoreError::Backend(format!(
+                "{err}; rolling back the import failed: {rollback}"
+            ))),
+        },
+    }
+}
+
+fn load_rows(store: &mut dyn Store, snapshot: StoreSnapshot) -> Result<Vec<String>, StoreError> {
+    for run in snapshot.runs {
+        store.put_run(run)?;
+    }
+    for archived in snapshot.archived_runs {
+        store.put_archived_run(archived)?;
+    }
+    for mapping in snapshot.approval_mappings {
+        store.map_approval_to_run(&mapping.approval_id, &mapping.run_id)?;
+    }
+    let mut evicted = Vec::new();
+    for entry in snapshot.idempotency_records {
+        let record = IdempotencyRecord {
+            payload_hash: entry.payload_hash,
+            response_json: entry.response_json,
+            created_at: entry.created_at,
+            payload_json: entry.payload_json,
+        };
+        evicted.extend(store.put_idempotency(&entry.key, record, DateTime::<Utc>::MIN_UTC)?);
+    }
+    Ok(evicted)
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::SqliteStore;
+    use arbiter_config::SqliteTuning;
+    use arbiter_contracts::{Run, RunEnvelope, RunStatus};
+
+    #[test]
+    fn failed_import_leaves_the_store_empty() {
+        let mut source = SqliteStore::open(":memory:", &SqliteTuning::default()).unwrap();
+        let run = RunEnvelope {
+            run: Run {
+                run_id: "run_1".to_string(),
+                request_id: "req_1".to_string(),
+                requester: "alice".to_string(),
+                source: "cli".to_string(),
+                objective: "deploy".to_string(),
+                environment: "dev".to_string(),
+                status: RunStatus::Accepted,
+                created_at: "2026-01-01T00:00:00Z".to_string(),
+                updated_at: "2026-01-01T00:00:00Z".to_string(),
+                risk_summary: serde_json::Value::Null,
+            },
+            steps: Vec::new(),
+            approvals: Vec::new(),
+            permits: Vec::new(),
+        };
+        source.put_run(run).unwrap();
+        let mut snapshot = export_snapshot(&source).unwrap();
+        // A second run reusing the request id breaks the unique index
+        // after the first row is already written.
+        let mut duplicate = snapshot.runs[0].clone();
+        duplicate.run.run_id = "run_2".to_string();
+        snapshot.runs.push(duplicate);
+
+        let mut target = SqliteStore::open(":memory:", &SqliteTuning::default()).unwrap();
+        assert!(matches!(
+            import_snapshot(&mut target, snapshot.clone()),
+            Err(StoreError::Backend(_))
+        ));
+        assert_eq!(target.stats().unwrap().runs, 0);
+
+        snapshot.runs.pop();
+        import_snapshot(&mut target, snapshot).unwrap();
+        assert_eq!(target.stats().unwrap().runs, 1);
+    }
+}
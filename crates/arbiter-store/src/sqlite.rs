@@ -0,0 +1,625 @@
+use arbiter_config::SqliteTuning;
+use arbiter_contracts::{ArchivedRun, RunEnvelope, StoreStats};
+use chrono::{DateTime, Utc};
+use rusqlite::backup::Backup;
+use rusqlite::{params, Connection, DatabaseName};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::migrations::{current_version, migrate, SQLITE_SCHEMA_VERSION};
+use crate::{IdMatch, IdempotencyRecord, Store, StoreError};
+
+/// Adds every id of the run bound to `?1` (run id) and `?2` (envelope
+/// JSON) to `lookup_ids`. Migration 4 backfills existing runs the same way.
+const INDEX_RUN_IDS: &str = "
+    WITH src(run_id, envelope_json) AS (VALUES (?1, ?2))
+    INSERT OR IGNORE INTO lookup_ids (id, kind, run_id)
+    SELECT id, kind, run_id FROM (
+        SELECT run_id AS id, 'run' AS kind, run_id FROM src
+        UNION ALL SELECT json_extract(envelope_json, '$.run.request_id'), 'operation_request', run_id FROM src
+        UNION ALL SELECT json_extract(s.value, '$.step_id'), 'step', run_id
+            FROM src, json_each(envelope_json, '$.steps') AS s
+        UNION ALL SELECT json_extract(s.value, '$.intent.client_step_id'), 'step', run_id
+            FROM src, json_each(envelope_json, '$.steps') AS s
+        UNION ALL SELECT json_extract(s.value, '$.decision.decision_id'), 'decision', run_id
+            FROM src, json_each(envelope_json, '$.steps') AS s
+        UNION ALL SELECT json_extract(s.value, '$.permit.permit_id'), 'permit', run_id
+            FROM src, json_each(envelope_json, '$.steps') AS s
+        UNION ALL SELECT json_extract(a.value, '$.approval_id'), 'approval', run_id
+            FROM src, json_each(envelope_json, '$.approvals') AS a
+    ) WHERE id IS NOT NULL;
+";
+
+/// Opens the database with the configured pragmas. The busy timeout lets a
+/// writer wait out another process's lock (a backup, an overlapping rollout)
+/// instead of failing with `SQLITE_BUSY`.
+fn open_connection(
+    sqlite_path: &str,
+    tuning: &SqliteTuning,
+    encryption_key: Option<&str>,
+) -> Result<Connection, String> {
+    let conn = Connection::open(sqlite_path)
+        .map_err(|err| format!("failed to open sqlite database: {err}"))?;
+    if let Some(key) = encryption_key {
+        unlock(&conn, key)
+            .map_err(|err| format!("failed to unlock encrypted sqlite database: {err}"))?;
+    }
+    conn.busy_timeout(tuning.busy_timeout)
+        .map_err(|err| format!("failed to set sqlite busy_timeout: {err}"))?;
+    // Only takes effect before the first table is created; older databases
+    // are switched over by the first `vacuum`.
+    conn.pragma_update(None, "auto_vacuum", "incremental")
+        .map_err(|err| format!("failed to set sqlite auto_vacuum: {err}"))?;
+    conn.pragma_update(None, "journal_mode", &tuning.journal_mode)
+        .map_err(|err| format!("failed to set sqlite journal_mode: {err}"))?;
+    conn.pragma_update(None, "synchronous", &tuning.synchronous)
+        .map_err(|err| format!("failed to set sqlite synchronous: {err}"))?;
+    Ok(conn)
+}
+
+/// Resolves `store.sqlite.encryption_key_env`. The key never appears in
+/// the config file itself.
+fn encryption_key(tuning: &SqliteTuning) -> Result<Option<String>, String> {
+    let Some(var) = tuning.encryption_key_env.as_deref() else {
+        return Ok(None);
+    };
+    if !cfg!(feature = "sqlcipher") {
+        return Err(
+            "store.sqlite.encryption_key_env requires arbiter built with the sqlcipher feature"
+                .to_string(),
+        );
+    }
+    match std::env::var(var) {
+        Ok(key) if !key.is_empty() => Ok(Some(key)),
+        _ => Err(format!(
+            "store.sqlite.encryption_key_env names {var}, which is not set"
+        )),
+    }
+}
+
+/// Keys the connection, which must happen before any other statement, then
+/// reads the schema so a wrong key (or a plaintext database) fails here
+/// rather than on the first query.
+fn unlock(conn: &Connection, key: &str) -> rusqlite::Result<()> {
+    conn.pragma_update(None, "key", key)?;
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
+}
+
+pub struct SqliteStore {
+    conn: Connection,
+    schema_version: i64,
+    // Kept so backups are encrypted with the same key as the database.
+    encryption_key: Option<String>,
+}
+
+impl SqliteStore {
+    fn count_rows(&self, table: &str) -> Result<u64, StoreError> {
+        let count: i64 = self
+            .conn
+            .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        Ok(count as u64)
+    }
+}
+
+impl SqliteStore {
+    /// Opens (or creates) the database at `sqlite_path` and applies any
+    /// pending schema migrations.
+    pub fn open(sqlite_path: &str, tuning: &SqliteTuning) -> Result<Self, String> {
+        let encryption_key = encryption_key(tuning)?;
+        let mut conn = open_connection(sqlite_path, tuning, encryption_key.as_deref())?;
+        let schema_version = migrate(&mut conn)?;
+        Ok(Self {
+            conn,
+            schema_version,
+            encryption_key,
+        })
+    }
+
+    /// Opens the database at `sqlite_path` without migrating it, for
+    /// commands that read a store while `arbiter serve` owns it.
+    pub fn open_without_migrating(
+        sqlite_path: &str,
+        tuning: &SqliteTuning,
+    ) -> Result<Self, String> {
+        let encryption_key = encryption_key(tuning)?;
+        let conn = open_connection(sqlite_path, tuning, encryption_key.as_deref())?;
+        let schema_version = current_version(&conn)?;
+        Ok(Self {
+            conn,
+            schema_version,
+            encryption_key,
+        })
+    }
+}
+
+impl Store for SqliteStore {
+    fn kind(&self) -> &str {
+        "sqlite"
+    }
+
+    fn get_run(&self, run_id: &str) -> Result<Option<RunEnvelope>, StoreError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT envelope_json FROM runs WHERE run_id = ?1")
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let row = stmt
+            .query_row(params![run_id], |row| row.get::<_, String>(0))
+            .ok();
+        let Some(text) = row else {
+            return Ok(None);
+        };
+        let run: RunEnvelope =
+            serde_json::from_str(&text).map_err(|err| StoreError::Backend(err.to_string()))?;
+        Ok(Some(run))
+    }
+
+    fn put_run(&mut self, run: RunEnvelope) -> Result<Vec<String>, StoreError> {
+        let run_id = run.run.run_id.clone();
+        let request_id = run.run.request_id.clone();
+        let json =
+            serde_json::to_string(&run).map_err(|err| StoreError::Backend(err.to_string()))?;
+        let tx = self
+            .conn
+            .savepoint()
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        tx.execute(
+            "INSERT INTO runs (run_id, request_id, envelope_json) VALUES (?1, ?2, ?3)
+             ON CONFLICT(run_id) DO UPDATE SET request_id=excluded.request_id, envelope_json=excluded.envelope_json",
+            params![run_id, request_id, json],
+        )
+        .map_err(|err| StoreError::Backend(err.to_string()))?;
+        tx.execute(INDEX_RUN_IDS, params![run_id, json])
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        tx.commit()
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        Ok(Vec::new())
+    }
+
+    fn find_run_by_request_id(&self, request_id: &str) -> Result<Option<RunEnvelope>, StoreError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT envelope_json FROM runs WHERE request_id = ?1
+                 UNION ALL
+                 SELECT envelope_json FROM runs_archive WHERE request_id = ?1",
+            )
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let row = stmt
+            .query_row(params![request_id], |row| row.get::<_, String>(0))
+            .ok();
+        let Some(text) = row else {
+            return Ok(None);
+        };
+        let run: RunEnvelope =
+            serde_json::from_str(&text).map_err(|err| StoreError::Backend(err.to_string()))?;
+        Ok(Some(run))
+    }
+
+    fn get_archived_run(&self, run_id: &str) -> Result<Option<RunEnvelope>, StoreError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT envelope_json FROM runs_archive WHERE run_id = ?1")
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let row = stmt
+            .query_row(params![run_id], |row| row.get::<_, String>(0))
+            .ok();
+        let Some(text) = row else {
+            return Ok(None);
+        };
+        let run: RunEnvelope =
+            serde_json::from_str(&text).map_err(|err| StoreError::Backend(err.to_string()))?;
+        Ok(Some(run))
+    }
+
+    fn archive_terminal_runs(
+        &mut self,
+        threshold: DateTime<Utc>,
+        archived_at: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<usize, StoreError> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let archived = tx
+            .execute(
+                "INSERT INTO runs_archive (run_id, request_id, envelope_json, archived_at)
+                 SELECT run_id, request_id, envelope_json, ?2 FROM runs
+                 WHERE json_extract(envelope_json, '$.run.status') IN ('succeeded', 'failed', 'cancelled')
+                   AND json_extract(envelope_json, '$.run.updated_at') < ?1
+                 LIMIT ?3",
+                params![
+                    threshold.to_rfc3339(),
+                    archived_at.to_rfc3339(),
+                    limit as i64
+                ],
+            )
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        tx.execute(
+            "DELETE FROM runs WHERE run_id IN (SELECT run_id FROM runs_archive)",
+            [],
+        )
+        .map_err(|err| StoreError::Backend(err.to_string()))?;
+        tx.commit()
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        Ok(archived)
+    }
+
+    fn map_approval_to_run(&mut self, approval_id: &str, run_id: &str) -> Result<(), StoreError> {
+        self.conn
+            .execute(
+                "INSERT INTO approvals (approval_id, run_id) VALUES (?1, ?2)
+                 ON CONFLICT(approval_id) DO UPDATE SET run_id=excluded.run_id",
+                params![approval_id, run_id],
+            )
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        Ok(())
+    }
+
+    fn run_id_for_approval(&self, approval_id: &str) -> Result<Option<String>, StoreError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT run_id FROM approvals WHERE approval_id = ?1")
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let row = stmt
+            .query_row(params![approval_id], |row| row.get::<_, String>(0))
+            .ok();
+        Ok(row)
+    }
+
+    fn remove_approval_mapping(&mut self, approval_id: &str) -> Result<(), StoreError> {
+        self.conn
+            .execute(
+                "DELETE FROM approvals WHERE approval_id = ?1",
+                params![approval_id],
+            )
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        Ok(())
+    }
+
+    fn list_runs(&self) -> Result<Vec<RunEnvelope>, StoreError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT envelope_json FROM runs ORDER BY run_id")
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let mut runs = Vec::new();
+        for row in rows {
+            let text = row.map_err(|err| StoreError::Backend(err.to_string()))?;
+            runs.push(
+                serde_json::from_str(&text).map_err(|err| StoreError::Backend(err.to_string()))?,
+            );
+        }
+        Ok(runs)
+    }
+
+    fn list_approval_mappings(&self) -> Result<Vec<(String, String)>, StoreError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT approval_id, run_id FROM approvals ORDER BY approval_id")
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|err| StoreError::Backend(err.to_string()))
+    }
+
+    fn list_archived_runs(&self) -> Result<Vec<ArchivedRun>, StoreError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT archived_at, envelope_json FROM runs_archive ORDER BY run_id")
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let mut runs = Vec::new();
+        for row in rows {
+            let (archived_at, text) = row.map_err(|err| StoreError::Backend(err.to_string()))?;
+            runs.push(ArchivedRun {
+                archived_at,
+                envelope: serde_json::from_str(&text)
+                    .map_err(|err| StoreError::Backend(err.to_string()))?,
+            });
+        }
+        Ok(runs)
+    }
+
+    fn put_archived_run(&mut self, archived: ArchivedRun) -> Result<(), StoreError> {
+        let json = serde_json::to_string(&archived.envelope)
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let tx = self
+            .conn
+            .savepoint()
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        tx.execute(
+            "INSERT INTO runs_archive (run_id, request_id, envelope_json, archived_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(run_id) DO UPDATE SET request_id=excluded.request_id,
+                 envelope_json=excluded.envelope_json, archived_at=excluded.archived_at",
+            params![
+                archived.envelope.run.run_id,
+                archived.envelope.run.request_id,
+                json,
+                archived.archived_at
+            ],
+        )
+        .map_err(|err| StoreError::Backend(err.to_string()))?;
+        tx.execute(INDEX_RUN_IDS, params![archived.envelope.run.run_id, json])
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        tx.commit()
+            .map_err(|err| StoreError::Backend(err.to_string()))
+    }
+
+    fn get_idempotency(&self, key: &str) -> Result<Option<IdempotencyRecord>, StoreError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT payload_hash, response_json, created_at, payload_json FROM idempotency
+                 WHERE idem_key = ?1",
+            )
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let row = stmt
+            .query_row(params![key], |row| {
+                Ok(IdempotencyRecord {
+                    payload_hash: row.get(0)?,
+                    response_json: row.get(1)?,
+                    created_at: row.get(2)?,
+                    payload_json: row.get(3)?,
+                })
+            })
+            .ok();
+        Ok(row)
+    }
+
+    fn list_idempotency(&self) -> Result<Vec<(String, IdempotencyRecord)>, StoreError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT idem_key, payload_hash, response_json, created_at, payload_json
+                 FROM idempotency ORDER BY idem_key",
+            )
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    IdempotencyRecord {
+                        payload_hash: row.get(1)?,
+                        response_json: row.get(2)?,
+                        created_at: row.get(3)?,
+                        payload_json: row.get(4)?,
+                    },
+                ))
+            })
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|err| StoreError::Backend(err.to_string()))
+    }
+
+    fn put_idempotency(
+        &mut self,
+        key: &str,
+        record: IdempotencyRecord,
+        expired_before: DateTime<Utc>,
+    ) -> Result<Vec<String>, StoreError> {
+        self.conn
+            .execute(
+                "INSERT INTO idempotency
+                     (idem_key, payload_hash, response_json, created_at, payload_json)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(idem_key) DO UPDATE SET
+                     payload_hash = excluded.payload_hash,
+                     response_json = excluded.response_json,
+                     created_at = excluded.created_at,
+                     payload_json = excluded.payload_json
+                 WHERE idempotency.created_at < ?6",
+                params![
+                    key,
+                    record.payload_hash,
+                    record.response_json,
+                    record.created_at,
+                    record.payload_json,
+                    expired_before.to_rfc3339()
+                ],
+            )
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        Ok(Vec::new())
+    }
+
+    fn prune_idempotency(
+        &mut self,
+        expired_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<usize, StoreError> {
+        self.conn
+            .execute(
+                "DELETE FROM idempotency WHERE idem_key IN (
+                     SELECT idem_key FROM idempotency WHERE created_at < ?1 LIMIT ?2
+                 )",
+                params![expired_before.to_rfc3339(), limit as i64],
+            )
+            .map_err(|err| StoreError::Backend(err.to_string()))
+    }
+
+    fn schema_version(&self) -> Option<i64> {
+        Some(self.schema_version)
+    }
+
+    fn supported_schema_version(&self) -> Option<i64> {
+        Some(SQLITE_SCHEMA_VERSION)
+    }
+
+    fn backup(&self, out_path: &str) -> Result<(), StoreError> {
+        if std::path::Path::new(out_path).exists() {
+            return Err(StoreError::Conflict(format!(
+                "backup target already exists: {out_path}"
+            )));
+        }
+        let Some(key) = self.encryption_key.as_deref() else {
+            return self
+                .conn
+                .backup(DatabaseName::Main, out_path, None)
+                .map_err(|err| StoreError::Backend(format!("sqlite backup failed: {err}")));
+        };
+        let mut dst = Connection::open(out_path)
+            .map_err(|err| StoreError::Backend(format!("sqlite backup failed: {err}")))?;
+        dst.pragma_update(None, "key", key)
+            .and_then(|()| {
+                Backup::new(&self.conn, &mut dst)?.run_to_completion(5, Duration::ZERO, None)
+            })
+            .map_err(|err| StoreError::Backend(format!("sqlite backup failed: {err}")))
+    }
+
+    fn ping(&self) -> Result<(), StoreError> {
+        self.conn
+            .query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
+            .map_err(|err| StoreError::Backend(format!("sqlite ping failed: {err}")))
+    }
+
+    /// Databases created before incremental auto_vacuum was enabled get one
+    /// full VACUUM, which rewrites the file in incremental mode and reports
+    /// no pages; later calls release free pages `max_pages` at a time.
+    fn vacuum(&mut self, max_pages: usize) -> Result<usize, StoreError> {
+        let backend =
+            |err: rusqlite::Error| StoreError::Backend(format!("sqlite vacuum failed: {err}"));
+        let auto_vacuum: i64 = self
+            .conn
+            .query_row("PRAGMA auto_vacuum", [], |row| row.get(0))
+            .map_err(backend)?;
+        if auto_vacuum != 2 {
+            self.conn
+                .pragma_update(None, "auto_vacuum", "incremental")
+                .and_then(|()| self.conn.execute_batch("VACUUM"))
+                .map_err(backend)?;
+            return Ok(0);
+        }
+        let free_pages = || {
+            self.conn
+                .query_row("PRAGMA freelist_count", [], |row| row.get::<_, i64>(0))
+        };
+        let before = free_pages().map_err(backend)?;
+        // Each step of the pragma releases one page, so drain it.
+        self.conn
+            .prepare(&format!("PRAGMA incremental_vacuum({max_pages})"))
+            .and_then(|mut stmt| {
+                let mut rows = stmt.query([])?;
+                while rows.next()?.is_some() {}
+                Ok(())
+            })
+            .map_err(backend)?;
+        let after = free_pages().map_err(backend)?;
+        Ok((before - after).max(0) as usize)
+    }
+
+    fn analyze(&mut self) -> Result<(), StoreError> {
+        self.conn
+            .execute_batch("ANALYZE")
+            .map_err(|err| StoreError::Backend(format!("sqlite analyze failed: {err}")))
+    }
+
+    fn lookup_id(&self, id: &str) -> Result<Vec<IdMatch>, StoreError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT kind, run_id, EXISTS (SELECT 1 FROM runs_archive a WHERE a.run_id = l.run_id)
+                 FROM lookup_ids l WHERE id = ?1 ORDER BY run_id, kind",
+            )
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let rows = stmt
+            .query_map(params![id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, bool>(2)?,
+                ))
+            })
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let mut matches = Vec::new();
+        for row in rows {
+            let (kind, run_id, archived) =
+                row.map_err(|err| StoreError::Backend(err.to_string()))?;
+            let kind = serde_json::from_value(serde_json::Value::String(kind))
+                .map_err(|err| StoreError::Backend(format!("invalid lookup kind: {err}")))?;
+            matches.push(IdMatch {
+                kind,
+                run_id,
+                archived,
+            });
+        }
+        Ok(matches)
+    }
+
+    fn begin_batch(&mut self) -> Result<(), StoreError> {
+        self.conn
+            .execute_batch("BEGIN IMMEDIATE")
+            .map_err(|err| StoreError::Backend(err.to_string()))
+    }
+
+    fn commit_batch(&mut self) -> Result<(), StoreError> {
+        self.conn
+            .execute_batch("COMMIT")
+            .map_err(|err| StoreError::Backend(err.to_string()))
+    }
+
+    fn rollback_batch(&mut self) -> Result<(), StoreError> {
+        self.conn
+            .execute_batch("ROLLBACK")
+            .map_err(|err| StoreError::Backend(err.to_string()))
+    }
+
+    fn stats(&self) -> Result<StoreStats, StoreError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT json_extract(envelope_json, '$.run.status'), COUNT(*)
+                 FROM runs GROUP BY 1",
+            )
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        let runs_by_status = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+                    row.get::<_, i64>(1)? as u64,
+                ))
+            })
+            .map_err(|err| StoreError::Backend(err.to_string()))?
+            .collect::<Result<BTreeMap<String, u64>, _>>()
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        Ok(StoreStats {
+            kind: self.kind().to_string(),
+            runs: self.count_rows("runs")?,
+            archived_runs: self.count_rows("runs_archive")?,
+            approval_mappings: self.count_rows("approvals")?,
+            idempotency_records: self.count_rows("idempotency")?,
+            runs_by_status,
+        })
+    }
+
+    fn doctor(&self) -> Result<Vec<String>, StoreError> {
+        let mut out = vec!["store=sqlite".to_string()];
+        out.push(format!("runs={}", self.count_rows("runs")?));
+        out.push(format!(
+            "archived_runs={}",
+            self.count_rows("runs_archive")?
+        ));
+        out.push(format!(
+            "idempotency_records={}",
+            self.count_rows("idempotency")?
+        ));
+        let journal_mode: String = self
+            .conn
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .map_err(|err| StoreError::Backend(err.to_string()))?;
+        out.push(format!("journal_mode={journal_mode}"));
+        out.push(format!("encrypted={}", self.encryption_key.is_some()));
+        Ok(out)
+    }
+}
@@ -14,9 +14,12 @@ CARGO_FILES = [
     "crates/arbiter-contracts/Cargo.toml",
     "crates/arbiter-kernel/Cargo.toml",
     "crates/arbiter-server/Cargo.toml",
+    "crates/arbiter-soak/Cargo.toml",
+    "crates/arbiter-store/Cargo.toml",
 ]
 
 SCHEMA_FILES = [
+    "contracts/v1/ops.audit_record.schema.json",
     "contracts/v1/ops.contracts_metadata.schema.json",
     "config/config.schema.json",
 ]
@@ -1,16 +1,82 @@
+pub(crate) const USAGE: &str = "\
+Usage:
+  arbiter init [--out <path>] [--force]
+  arbiter serve --config <path> [--fd <listener-fd>] [--lenient]
+  arbiter config-validate [--config <path>] [--lenient]
+  arbiter config-docs
+  arbiter audit-verify [--path <path>] [--mirror-path <path>]
+  arbiter store-doctor [--config <path>] [--lenient]
+  arbiter store-backup [--config <path>] [--out <path>] [--lenient]
+  arbiter store-export [--config <path>] [--out <path>] [--lenient]
+  arbiter store-import [--config <path>] --in <path> [--lenient]
+  arbiter store-fsck [--config <path>] [--repair] [--lenient]
+  arbiter backup [--config <path>] --out <dir> [--lenient]
+  arbiter restore [--config <path>] --from <dir> [--lenient]
+  arbiter policy-diff --config-a <path> --config-b <path> --events <path> [--lenient]
+  arbiter policy-test [--config <path>] <case-file>...";
+
 pub(crate) enum Command {
+    Init {
+        out_path: String,
+        force: bool,
+    },
     Serve {
         config_path: String,
+        listener_fd: Option<i32>,
+        lenient: bool,
     },
     ConfigValidate {
         config_path: String,
+        lenient: bool,
     },
+    ConfigDocs,
     AuditVerify {
         audit_path: String,
         mirror_path: Option<String>,
     },
     StoreDoctor {
         config_path: String,
+        lenient: bool,
+    },
+    StoreBackup {
+        config_path: String,
+        out_path: String,
+        lenient: bool,
+    },
+    StoreExport {
+        config_path: String,
+        out_path: String,
+        lenient: bool,
+    },
+    StoreImport {
+        config_path: String,
+        in_path: String,
+        lenient: bool,
+    },
+    StoreFsck {
+        config_path: String,
+        repair: bool,
+        lenient: bool,
+    },
+    Backup {
+        config_path: String,
+        out_dir: String,
+        lenient: bool,
+    },
+    Restore {
+        config_path: String,
+        from_dir: String,
+        lenient: bool,
+    },
+    PolicyDiff {
+        config_a: String,
+        config_b: String,
+        events_path: String,
+        lenient: bool,
+    },
+    PolicyTest {
+        config_path: String,
+        case_paths: Vec<String>,
     },
     Invalid,
 }
@@ -22,6 +88,10 @@ where
     let mut args = args.into_iter();
     let cmd = args.next().unwrap_or_default();
 
+    if cmd == "init" {
+        return parse_init(args);
+    }
+
     if cmd == "audit-verify" {
         return parse_audit_verify(args);
     }
@@ -30,10 +100,46 @@ where
         return parse_config_validate(args);
     }
 
+    if cmd == "config-docs" {
+        return Command::ConfigDocs;
+    }
+
     if cmd == "store-doctor" {
         return parse_store_doctor(args);
     }
 
+    if cmd == "store-backup" {
+        return parse_store_backup(args);
+    }
+
+    if cmd == "store-export" {
+        return parse_store_export(args);
+    }
+
+    if cmd == "store-import" {
+        return parse_store_import(args);
+    }
+
+    if cmd == "store-fsck" {
+        return parse_store_fsck(args);
+    }
+
+    if cmd == "backup" {
+        return parse_backup(args);
+    }
+
+    if cmd == "restore" {
+        return parse_restore(args);
+    }
+
+    if cmd == "policy-diff" {
+        return parse_policy_diff(args);
+    }
+
+    if cmd == "policy-test" {
+        return parse_policy_test(args);
+    }
+
     if cmd == "serve" {
         return parse_serve(args);
     }
@@ -41,6 +147,22 @@ where
     Command::Invalid
 }
 
+fn parse_init(mut args: impl Iterator<Item = String>) -> Command {
+    let mut out_path = String::from("./arbiter.yaml");
+    let mut force = false;
+    while let Some(arg) = args.next() {
+        if arg == "--out" {
+            if let Some(v) = args.next() {
+                out_path = v;
+            }
+        }
+        if arg == "--force" {
+            force = true;
+        }
+    }
+    Command::Init { out_path, force }
+}
+
 fn parse_audit_verify(mut args: impl Iterator<Item = String>) -> Command {
     let mut audit_path = String::from("./arbiter-audit.jsonl");
     let mut mirror_path: Option<String> = None;
@@ -66,54 +188,367 @@ fn parse_audit_verify(mut args: impl Iterator<Item = String>) -> Command {
 
 fn parse_serve(mut args: impl Iterator<Item = String>) -> Command {
     let mut config_path = String::from("./config/example-config.yaml");
+    let mut listener_fd: Option<i32> = None;
+    let mut lenient = false;
     while let Some(arg) = args.next() {
         if arg == "--config" {
             if let Some(v) = args.next() {
                 config_path = v;
             }
         }
+        if arg == "--fd" {
+            match args.next().map(|v| v.parse::<i32>()) {
+                Some(Ok(fd)) if fd >= 0 => listener_fd = Some(fd),
+                _ => return Command::Invalid,
+            }
+        }
+        if arg == "--lenient" {
+            lenient = true;
+        }
+    }
+    Command::Serve {
+        config_path,
+        listener_fd,
+        lenient,
     }
-    Command::Serve { config_path }
 }
 
 fn parse_config_validate(mut args: impl Iterator<Item = String>) -> Command {
     let mut config_path = String::from("./config/example-config.yaml");
+    let mut lenient = false;
     while let Some(arg) = args.next() {
         if arg == "--config" {
             if let Some(v) = args.next() {
                 config_path = v;
             }
         }
+        if arg == "--lenient" {
+            lenient = true;
+        }
+    }
+    Command::ConfigValidate {
+        config_path,
+        lenient,
     }
-    Command::ConfigValidate { config_path }
 }
 
 fn parse_store_doctor(mut args: impl Iterator<Item = String>) -> Command {
     let mut config_path = String::from("./config/example-config.yaml");
+    let mut lenient = false;
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            if let Some(v) = args.next() {
+                config_path = v;
+            }
+        }
+        if arg == "--lenient" {
+            lenient = true;
+        }
+    }
+    Command::StoreDoctor {
+        config_path,
+        lenient,
+    }
+}
+
+fn parse_store_backup(mut args: impl Iterator<Item = String>) -> Command {
+    let mut config_path = String::from("./config/example-config.yaml");
+    let mut out_path = String::from("./arbiter-store-backup.db");
+    let mut lenient = false;
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            if let Some(v) = args.next() {
+                config_path = v;
+            }
+        }
+        if arg == "--out" {
+            if let Some(v) = args.next() {
+                out_path = v;
+            }
+        }
+        if arg == "--lenient" {
+            lenient = true;
+        }
+    }
+    Command::StoreBackup {
+        config_path,
+        out_path,
+        lenient,
+    }
+}
+
+fn parse_store_export(mut args: impl Iterator<Item = String>) -> Command {
+    let mut config_path = String::from("./config/example-config.yaml");
+    let mut out_path = String::from("./arbiter-store-snapshot.json");
+    let mut lenient = false;
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            if let Some(v) = args.next() {
+                config_path = v;
+            }
+        }
+        if arg == "--out" {
+            if let Some(v) = args.next() {
+                out_path = v;
+            }
+        }
+        if arg == "--lenient" {
+            lenient = true;
+        }
+    }
+    Command::StoreExport {
+        config_path,
+        out_path,
+        lenient,
+    }
+}
+
+fn parse_store_import(mut args: impl Iterator<Item = String>) -> Command {
+    let mut config_path = String::from("./config/example-config.yaml");
+    let mut in_path: Option<String> = None;
+    let mut lenient = false;
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            if let Some(v) = args.next() {
+                config_path = v;
+            }
+        }
+        if arg == "--in" {
+            in_path = args.next();
+        }
+        if arg == "--lenient" {
+            lenient = true;
+        }
+    }
+    match in_path {
+        Some(in_path) => Command::StoreImport {
+            config_path,
+            in_path,
+            lenient,
+        },
+        None => Command::Invalid,
+    }
+}
+
+fn parse_backup(mut args: impl Iterator<Item = String>) -> Command {
+    let mut config_path = String::from("./config/example-config.yaml");
+    let mut out_dir: Option<String> = None;
+    let mut lenient = false;
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            if let Some(v) = args.next() {
+                config_path = v;
+            }
+        }
+        if arg == "--out" {
+            out_dir = args.next();
+        }
+        if arg == "--lenient" {
+            lenient = true;
+        }
+    }
+    match out_dir {
+        Some(out_dir) => Command::Backup {
+            config_path,
+            out_dir,
+            lenient,
+        },
+        None => Command::Invalid,
+    }
+}
+
+fn parse_restore(mut args: impl Iterator<Item = String>) -> Command {
+    let mut config_path = String::from("./config/example-config.yaml");
+    let mut from_dir: Option<String> = None;
+    let mut lenient = false;
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            if let Some(v) = args.next() {
+                config_path = v;
+            }
+        }
+        if arg == "--from" {
+            from_dir = args.next();
+        }
+        if arg == "--lenient" {
+            lenient = true;
+        }
+    }
+    match from_dir {
+        Some(from_dir) => Command::Restore {
+            config_path,
+            from_dir,
+            lenient,
+        },
+        None => Command::Invalid,
+    }
+}
+
+fn parse_store_fsck(mut args: impl Iterator<Item = String>) -> Command {
+    let mut config_path = String::from("./config/example-config.yaml");
+    let mut repair = false;
+    let mut lenient = false;
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            if let Some(v) = args.next() {
+                config_path = v;
+            }
+        }
+        if arg == "--repair" {
+            repair = true;
+        }
+        if arg == "--lenient" {
+            lenient = true;
+        }
+    }
+    Command::StoreFsck {
+        config_path,
+        repair,
+        lenient,
+    }
+}
+
+fn parse_policy_diff(mut args: impl Iterator<Item = String>) -> Command {
+    let mut config_a: Option<String> = None;
+    let mut config_b: Option<String> = None;
+    let mut events_path: Option<String> = None;
+    let mut lenient = false;
+    while let Some(arg) = args.next() {
+        if arg == "--config-a" {
+            config_a = args.next();
+        }
+        if arg == "--config-b" {
+            config_b = args.next();
+        }
+        if arg == "--events" {
+            events_path = args.next();
+        }
+        if arg == "--lenient" {
+            lenient = true;
+        }
+    }
+    match (config_a, config_b, events_path) {
+        (Some(config_a), Some(config_b), Some(events_path)) => Command::PolicyDiff {
+            config_a,
+            config_b,
+            events_path,
+            lenient,
+        },
+        _ => Command::Invalid,
+    }
+}
+
+fn parse_policy_test(mut args: impl Iterator<Item = String>) -> Command {
+    let mut config_path = String::from("./config/example-config.yaml");
+    let mut case_paths = Vec::new();
     while let Some(arg) = args.next() {
         if arg == "--config" {
             if let Some(v) = args.next() {
                 config_path = v;
             }
+            continue;
         }
+        case_paths.push(arg);
+    }
+    if case_paths.is_empty() {
+        return Command::Invalid;
+    }
+    Command::PolicyTest {
+        config_path,
+        case_paths,
     }
-    Command::StoreDoctor { config_path }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{parse_args, Command};
+    use std::time::Duration;
 
     #[test]
     fn parse_serve_with_default_config() {
         match parse_args(vec!["serve".to_string()]) {
-            Command::Serve { config_path } => {
+            Command::Serve { config_path, .. } => {
                 assert_eq!(config_path, "./config/example-config.yaml");
             }
             _ => panic!("unexpected command"),
         }
     }
 
+    #[test]
+    fn parse_init_with_out_and_force() {
+        match parse_args(vec![
+            "init".to_string(),
+            "--out".to_string(),
+            "./etc/arbiter.yaml".to_string(),
+            "--force".to_string(),
+        ]) {
+            Command::Init { out_path, force } => {
+                assert_eq!(out_path, "./etc/arbiter.yaml");
+                assert!(force);
+            }
+            _ => panic!("unexpected command"),
+        }
+    }
+
+    #[test]
+    fn starter_config_passes_validation() {
+        arbiter_config::parse_and_validate(arbiter_config::STARTER_CONFIG_YAML).unwrap();
+    }
+
+    #[test]
+    fn config_durations_accept_unit_suffixes_and_bare_integers() {
+        let with = |value: &str| {
+            arbiter_config::STARTER_CONFIG_YAML.replacen(
+                "server:\n",
+                &format!("server:\n  request_timeout: {value}\n"),
+                1,
+            )
+        };
+        let cfg = arbiter_config::parse_and_validate(&with("\"3s\"")).unwrap();
+        assert_eq!(cfg.server.request_timeout, Duration::from_secs(3));
+        let cfg = arbiter_config::parse_and_validate(&with("1500")).unwrap();
+        assert_eq!(cfg.server.request_timeout, Duration::from_millis(1500));
+        assert!(arbiter_config::parse_and_validate(&with("\"0s\"")).is_err());
+        assert!(arbiter_config::parse_and_validate(&with("\"3 weeks\"")).is_err());
+
+        // Keys named after their unit still load, in that unit.
+        let legacy = arbiter_config::STARTER_CONFIG_YAML
+            .replacen("server:\n", "server:\n  request_timeout_ms: 1500\n", 1)
+            .replacen("permit_ttl: 5m", "permit_ttl_seconds: 90", 1);
+        let cfg = arbiter_config::parse_and_validate(&legacy).unwrap();
+        assert_eq!(cfg.server.request_timeout, Duration::from_millis(1500));
+        assert_eq!(cfg.governance.permit_ttl, Duration::from_secs(90));
+    }
+
+    #[test]
+    fn reuse_port_requires_the_sqlite_store() {
+        let yaml = arbiter_config::STARTER_CONFIG_YAML.replacen(
+            "server:\n",
+            "server:\n  reuse_port: true\n",
+            1,
+        );
+        let err = arbiter_config::parse_and_validate(&yaml).unwrap_err();
+        assert!(err.to_string().contains("server.reuse_port"), "{err}");
+    }
+
+    #[test]
+    fn unknown_config_keys_are_rejected_unless_lenient() {
+        let yaml = arbiter_config::STARTER_CONFIG_YAML.replacen(
+            "server:\n",
+            "server:\n  startup_check:\n    enabeld: true\n  listen_adr: x\n",
+            1,
+        );
+        match arbiter_config::parse_and_validate(&yaml) {
+            Err(arbiter_config::ConfigError::UnknownKeys(keys)) => assert_eq!(
+                keys,
+                vec!["server.listen_adr", "server.startup_check.enabeld"]
+            ),
+            other => panic!("unexpected result: {other:?}"),
+        }
+        let (_, ignored) = arbiter_config::parse_and_validate_lenient(&yaml).unwrap();
+        assert_eq!(ignored.len(), 2);
+    }
+
     #[test]
     fn parse_audit_verify_with_paths() {
         match parse_args(vec![
@@ -148,6 +583,62 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_policy_diff_requires_all_paths() {
+        match parse_args(vec![
+            "policy-diff".to_string(),
+            "--config-a".to_string(),
+            "./a.yaml".to_string(),
+            "--config-b".to_string(),
+            "./b.yaml".to_string(),
+            "--events".to_string(),
+            "./captured.jsonl".to_string(),
+        ]) {
+            Command::PolicyDiff {
+                config_a,
+                config_b,
+                events_path,
+                ..
+            } => {
+                assert_eq!(config_a, "./a.yaml");
+                assert_eq!(config_b, "./b.yaml");
+                assert_eq!(events_path, "./captured.jsonl");
+            }
+            _ => panic!("unexpected command"),
+        }
+        assert!(matches!(
+            parse_args(vec!["policy-diff".to_string()]),
+            Command::Invalid
+        ));
+    }
+
+    #[test]
+    fn parse_policy_test_collects_case_files() {
+        match parse_args(vec![
+            "policy-test".to_string(),
+            "policies/tests/a.yaml".to_string(),
+            "--config".to_string(),
+            "./custom.yaml".to_string(),
+            "policies/tests/b.yaml".to_string(),
+        ]) {
+            Command::PolicyTest {
+                config_path,
+                case_paths,
+            } => {
+                assert_eq!(config_path, "./custom.yaml");
+                assert_eq!(
+                    case_paths,
+                    vec!["policies/tests/a.yaml", "policies/tests/b.yaml"]
+                );
+            }
+            _ => panic!("unexpected command"),
+        }
+        assert!(matches!(
+            parse_args(vec!["policy-test".to_string()]),
+            Command::Invalid
+        ));
+    }
+
     #[test]
     fn parse_invalid_command() {
         match parse_args(vec!["unknown".to_string()]) {
@@ -162,31 +653,182 @@ mod tests {
             "config-validate".to_string(),
             "--config".to_string(),
             "./custom.yaml".to_string(),
+            "--lenient".to_string(),
         ]) {
-            Command::ConfigValidate { config_path } => {
+            Command::ConfigValidate {
+                config_path,
+                lenient,
+            } => {
                 assert_eq!(config_path, "./custom.yaml");
+                assert!(lenient);
             }
             _ => panic!("unexpected command"),
         }
     }
 
+    #[test]
+    fn parse_config_docs_command() {
+        assert!(matches!(
+            parse_args(vec!["config-docs".to_string()]),
+            Command::ConfigDocs
+        ));
+    }
+
     #[test]
     fn parse_store_doctor_command() {
         match parse_args(vec!["store-doctor".to_string()]) {
-            Command::StoreDoctor { config_path } => {
+            Command::StoreDoctor {
+                config_path,
+                lenient,
+            } => {
+                assert_eq!(config_path, "./config/example-config.yaml");
+                assert!(!lenient);
+            }
+            _ => panic!("unexpected command"),
+        }
+    }
+
+    #[test]
+    fn parse_store_backup_command() {
+        match parse_args(vec![
+            "store-backup".to_string(),
+            "--config".to_string(),
+            "./custom.yaml".to_string(),
+            "--out".to_string(),
+            "./backup.db".to_string(),
+        ]) {
+            Command::StoreBackup {
+                config_path,
+                out_path,
+                ..
+            } => {
+                assert_eq!(config_path, "./custom.yaml");
+                assert_eq!(out_path, "./backup.db");
+            }
+            _ => panic!("unexpected command"),
+        }
+    }
+
+    #[test]
+    fn parse_backup_and_restore_commands() {
+        match parse_args(vec![
+            "backup".to_string(),
+            "--out".to_string(),
+            "/var/backups/arbiter".to_string(),
+        ]) {
+            Command::Backup {
+                config_path,
+                out_dir,
+                ..
+            } => {
                 assert_eq!(config_path, "./config/example-config.yaml");
+                assert_eq!(out_dir, "/var/backups/arbiter");
             }
             _ => panic!("unexpected command"),
         }
+        match parse_args(vec![
+            "restore".to_string(),
+            "--from".to_string(),
+            "/var/backups/arbiter".to_string(),
+            "--lenient".to_string(),
+        ]) {
+            Command::Restore {
+                from_dir, lenient, ..
+            } => {
+                assert_eq!(from_dir, "/var/backups/arbiter");
+                assert!(lenient);
+            }
+            _ => panic!("unexpected command"),
+        }
+        assert!(matches!(
+            parse_args(vec!["backup".to_string()]),
+            Command::Invalid
+        ));
+        assert!(matches!(
+            parse_args(vec!["restore".to_string()]),
+            Command::Invalid
+        ));
+    }
+
+    #[test]
+    fn parse_store_export_and_import_commands() {
+        match parse_args(vec!["store-export".to_string()]) {
+            Command::StoreExport { out_path, .. } => {
+                assert_eq!(out_path, "./arbiter-store-snapshot.json");
+            }
+            _ => panic!("unexpected command"),
+        }
+        match parse_args(vec![
+            "store-import".to_string(),
+            "--config".to_string(),
+            "./custom.yaml".to_string(),
+            "--in".to_string(),
+            "./snapshot.json".to_string(),
+        ]) {
+            Command::StoreImport {
+                config_path,
+                in_path,
+                ..
+            } => {
+                assert_eq!(config_path, "./custom.yaml");
+                assert_eq!(in_path, "./snapshot.json");
+            }
+            _ => panic!("unexpected command"),
+        }
+        assert!(matches!(
+            parse_args(vec!["store-import".to_string()]),
+            Command::Invalid
+        ));
+    }
+
+    #[test]
+    fn parse_store_fsck_command() {
+        match parse_args(vec!["store-fsck".to_string()]) {
+            Command::StoreFsck {
+                config_path,
+                repair,
+                ..
+            } => {
+                assert_eq!(config_path, "./config/example-config.yaml");
+                assert!(!repair);
+            }
+            _ => panic!("unexpected command"),
+        }
+        match parse_args(vec!["store-fsck".to_string(), "--repair".to_string()]) {
+            Command::StoreFsck { repair, .. } => assert!(repair),
+            _ => panic!("unexpected command"),
+        }
     }
 
     #[test]
     fn parse_serve_missing_config_value_keeps_default() {
         match parse_args(vec!["serve".to_string(), "--config".to_string()]) {
-            Command::Serve { config_path } => {
+            Command::Serve { config_path, .. } => {
                 assert_eq!(config_path, "./config/example-config.yaml");
             }
             _ => panic!("unexpected command"),
         }
     }
+
+    #[test]
+    fn parse_serve_with_inherited_fd() {
+        match parse_args(vec![
+            "serve".to_string(),
+            "--fd".to_string(),
+            "3".to_string(),
+        ]) {
+            Command::Serve { listener_fd, .. } => {
+                assert_eq!(listener_fd, Some(3));
+            }
+            _ => panic!("unexpected command"),
+        }
+        match parse_args(vec![
+            "serve".to_string(),
+            "--fd".to_string(),
+            "abc".to_string(),
+        ]) {
+            Command::Invalid => {}
+            _ => panic!("unexpected command"),
+        }
+    }
 }
@@ -2,11 +2,18 @@ use std::env;
 
 mod cli;
 
-use crate::cli::{parse_args, Command};
+use crate::cli::{parse_args, Command, USAGE};
 
 #[tokio::main]
 async fn main() {
     match parse_args(env::args().skip(1)) {
+        Command::Init { out_path, force } => match write_starter_config(&out_path, force) {
+            Ok(()) => println!("wrote starter config: {out_path}"),
+            Err(e) => {
+                eprintln!("init failed: {e}");
+                std::process::exit(1);
+            }
+        },
         Command::AuditVerify {
             audit_path,
             mirror_path,
@@ -22,37 +29,225 @@ async fn main() {
                 std::process::exit(1);
             }
         },
-        Command::ConfigValidate { config_path } => {
-            match arbiter_config::load_and_validate(&config_path) {
-                Ok(_) => println!("config valid: {config_path}"),
+        Command::ConfigValidate {
+            config_path,
+            lenient,
+        } => match load_config(&config_path, lenient) {
+            Ok(_) => println!("config valid: {config_path}"),
+            Err(e) => {
+                eprintln!("config invalid: {e}");
+                std::process::exit(1);
+            }
+        },
+        Command::ConfigDocs => match arbiter_config::config_docs() {
+            Ok(docs) => print!("{docs}"),
+            Err(e) => {
+                eprintln!("config docs failed: {e}");
+                std::process::exit(1);
+            }
+        },
+        Command::StoreDoctor {
+            config_path,
+            lenient,
+        } => {
+            let cfg = match load_config(&config_path, lenient) {
+                Ok(v) => v,
                 Err(e) => {
-                    eprintln!("config invalid: {e}");
+                    eprintln!("failed to load config: {e}");
+                    std::process::exit(1);
+                }
+            };
+            match arbiter_server::doctor(cfg).await {
+                Ok(lines) => {
+                    for line in lines {
+                        println!("{line}");
+                    }
+                }
+                Err(e) => {
+                    eprintln!("store doctor failed: {e}");
                     std::process::exit(1);
                 }
             }
         }
-        Command::StoreDoctor { config_path } => {
-            let cfg = match arbiter_config::load_and_validate(&config_path) {
+        Command::StoreBackup {
+            config_path,
+            out_path,
+            lenient,
+        } => {
+            let cfg = match load_config(&config_path, lenient) {
                 Ok(v) => v,
                 Err(e) => {
                     eprintln!("failed to load config: {e}");
                     std::process::exit(1);
                 }
             };
-            match arbiter_server::doctor(cfg).await {
-                Ok(lines) => {
+            match arbiter_server::backup(cfg, &out_path).await {
+                Ok(message) => println!("{message}"),
+                Err(e) => {
+                    eprintln!("store backup failed: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::StoreExport {
+            config_path,
+            out_path,
+            lenient,
+        } => {
+            let cfg = match load_config(&config_path, lenient) {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("failed to load config: {e}");
+                    std::process::exit(1);
+                }
+            };
+            match arbiter_server::export_store(cfg, &out_path).await {
+                Ok(message) => println!("{message}"),
+                Err(e) => {
+                    eprintln!("store export failed: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::StoreImport {
+            config_path,
+            in_path,
+            lenient,
+        } => {
+            let cfg = match load_config(&config_path, lenient) {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("failed to load config: {e}");
+                    std::process::exit(1);
+                }
+            };
+            match arbiter_server::import_store(cfg, &in_path).await {
+                Ok(message) => println!("{message}"),
+                Err(e) => {
+                    eprintln!("store import failed: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::StoreFsck {
+            config_path,
+            repair,
+            lenient,
+        } => {
+            let cfg = match load_config(&config_path, lenient) {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("failed to load config: {e}");
+                    std::process::exit(1);
+                }
+            };
+            match arbiter_server::fsck(cfg, repair).await {
+                Ok((lines, unresolved)) => {
                     for line in lines {
                         println!("{line}");
                     }
+                    if unresolved > 0 {
+                        std::process::exit(1);
+                    }
                 }
                 Err(e) => {
-                    eprintln!("store doctor failed: {e}");
+                    eprintln!("store fsck failed: {e}");
                     std::process::exit(1);
                 }
             }
         }
-        Command::Serve { config_path } => {
-            let cfg = match arbiter_config::load_and_validate(&config_path) {
+        Command::Backup {
+            config_path,
+            out_dir,
+            lenient,
+        } => {
+            let cfg = match load_config(&config_path, lenient) {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("failed to load config: {e}");
+                    std::process::exit(1);
+                }
+            };
+            match arbiter_server::backup_to_dir(cfg, &out_dir).await {
+                Ok(message) => println!("{message}"),
+                Err(e) => {
+                    eprintln!("backup failed: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Restore {
+            config_path,
+            from_dir,
+            lenient,
+        } => {
+            let cfg = match load_config(&config_path, lenient) {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("failed to load config: {e}");
+                    std::process::exit(1);
+                }
+            };
+            match arbiter_server::restore_from_dir(cfg, &from_dir).await {
+                Ok(message) => println!("{message}"),
+                Err(e) => {
+                    eprintln!("restore failed: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::PolicyDiff {
+            config_a,
+            config_b,
+            events_path,
+            lenient,
+        } => {
+            let load = |path: &str| match load_config(path, lenient) {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("failed to load config {path}: {e}");
+                    std::process::exit(1);
+                }
+            };
+            let (cfg_a, cfg_b) = (load(&config_a), load(&config_b));
+            match arbiter_server::policy_diff(&cfg_a, &cfg_b, &events_path) {
+                Ok(report) => println!("{report}"),
+                Err(e) => {
+                    eprintln!("policy diff failed: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::PolicyTest {
+            config_path,
+            case_paths,
+        } => {
+            let config_text = match std::fs::read_to_string(&config_path) {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("failed to read config {config_path}: {e}");
+                    std::process::exit(1);
+                }
+            };
+            match arbiter_server::policy_test(&config_text, &case_paths) {
+                Ok(report) => {
+                    println!("{report}");
+                    if report.failed() > 0 {
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("policy test failed: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Serve {
+            config_path,
+            listener_fd,
+            lenient,
+        } => {
+            let cfg = match load_config(&config_path, lenient) {
                 Ok(v) => v,
                 Err(e) => {
                     eprintln!("failed to load config: {e}");
@@ -60,16 +255,45 @@ async fn main() {
                 }
             };
 
-            if let Err(e) = arbiter_server::serve(cfg).await {
+            if let Err(e) = arbiter_server::serve_with_listener_fd(cfg, listener_fd).await {
                 eprintln!("server exited with error: {e}");
                 std::process::exit(1);
             }
         }
         Command::Invalid => {
-            eprintln!(
-                "Usage: arbiter serve --config <path> | arbiter config-validate [--config <path>] | arbiter audit-verify [--path <path>] [--mirror-path <path>] | arbiter store-doctor [--config <path>]"
-            );
+            eprintln!("{USAGE}");
             std::process::exit(2);
         }
     }
 }
+
+/// With `lenient`, keys the schema does not define are dropped with a
+/// warning instead of failing the load.
+fn load_config(
+    path: &str,
+    lenient: bool,
+) -> Result<arbiter_config::Config, arbiter_config::ConfigError> {
+    if !lenient {
+        return arbiter_config::load_and_validate(path);
+    }
+    let (cfg, ignored) = arbiter_config::load_and_validate_lenient(path)?;
+    for key in ignored {
+        eprintln!("warning: ignoring unknown config key {key} in {path}");
+    }
+    Ok(cfg)
+}
+
+fn write_starter_config(out_path: &str, force: bool) -> Result<(), String> {
+    let path = std::path::Path::new(out_path);
+    if path.exists() && !force {
+        return Err(format!(
+            "{out_path} already exists (use --force to overwrite)"
+        ));
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .map_err(|err| format!("create {} failed: {err}", parent.display()))?;
+    }
+    std::fs::write(path, arbiter_config::STARTER_CONFIG_YAML)
+        .map_err(|err| format!("write {out_path} failed: {err}"))
+}